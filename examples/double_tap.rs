@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use win_hotkeys::{Hotkey, HotkeyManager, TriggerBehavior, VKey};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+    let window = Duration::from_millis(300);
+
+    // Double tap of Shift, the Shift key still reaches other applications
+    hkm.register_hotkey(
+        Hotkey::new(VKey::LShift, [], || {
+            println!("Shift was double tapped");
+        })
+        .taps(2, window)
+        .behavior(TriggerBehavior::PassThrough),
+    )
+    .unwrap();
+
+    // Single and double tap on the same combination, the single tap action
+    // only runs once the tap window elapsed without a second tap
+    hkm.register_hotkey(Hotkey::new(VKey::J, [VKey::Control], || {
+        println!("CTRL + J tapped once");
+    }))
+    .unwrap();
+
+    hkm.register_hotkey(
+        Hotkey::new(VKey::J, [VKey::Control], || {
+            println!("CTRL + J tapped twice");
+        })
+        .taps(2, window),
+    )
+    .unwrap();
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    event_loop_thread.join().unwrap();
+}
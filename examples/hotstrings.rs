@@ -0,0 +1,32 @@
+use win_hotkeys::output::TextTemplate;
+use win_hotkeys::{HotkeyManager, Hotstring};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+
+    // Fires after typing "btw" followed by a space or a punctuation mark
+    hkm.register_hotstring(Hotstring::with_context("btw", |event| {
+        println!("Typed {:?}, ended by {:?}", event.typed, event.end_char);
+    }))
+    .unwrap();
+
+    // Fires as soon as "@@" is typed, even in the middle of a word
+    hkm.register_hotstring(
+        Hotstring::new("@@", || {
+            println!("Email shortcut");
+        })
+        .immediate()
+        .inside_words(),
+    )
+    .unwrap();
+
+    // Replaces "sig" with a signature, leaving the caret after "Dear "
+    hkm.register_hotstring(Hotstring::expansion(
+        "sig",
+        TextTemplate::parse("Dear {cursor},\n\nBest regards,\nJane ({date})").unwrap(),
+    ))
+    .unwrap();
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    event_loop_thread.join().unwrap();
+}
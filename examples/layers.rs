@@ -0,0 +1,60 @@
+use win_hotkeys::output::{InputEvent, InputSink, SendInputSink};
+use win_hotkeys::{Hotkey, HotkeyManager, LayerAction, VKey};
+
+const HYPER: [VKey; 4] = [VKey::Control, VKey::Shift, VKey::Menu, VKey::LWin];
+
+fn tap(key: VKey) -> impl Fn() + Send + Sync + 'static {
+    move || {
+        let events = [
+            InputEvent::Key { key, down: true },
+            InputEvent::Key { key, down: false },
+        ];
+        if let Err(err) = SendInputSink.send(&events) {
+            eprintln!("Failed to send {key:?}: {err}");
+        }
+    }
+}
+
+fn main() {
+    let hkm = HotkeyManager::current();
+
+    hkm.set_layer_change_listener(|change| {
+        println!("Active layers: {:?}", change.active);
+    });
+
+    // HYPER + N enters the navigation layer, ESCAPE leaves it
+    hkm.register_hotkey(
+        Hotkey::new(VKey::N, HYPER, || {}).switch_layer(LayerAction::Push("nav".to_owned())),
+    )
+    .unwrap();
+    hkm.register_hotkey(
+        Hotkey::new(VKey::Escape, [], || {})
+            .layer("nav")
+            .switch_layer(LayerAction::Pop),
+    )
+    .unwrap();
+
+    for (key, target) in [
+        (VKey::H, VKey::Left),
+        (VKey::J, VKey::Down),
+        (VKey::K, VKey::Up),
+        (VKey::L, VKey::Right),
+    ] {
+        hkm.register_hotkey(Hotkey::new(key, [], tap(target)).layer("nav"))
+            .unwrap();
+    }
+    hkm.register_hotkey(Hotkey::new(VKey::T, [], || println!("New tab action")).layer("nav"))
+        .unwrap();
+
+    // the media layer is only active while CAPSLOCK is held
+    hkm.register_hotkey(
+        Hotkey::new(VKey::Capital, [], || {})
+            .switch_layer(LayerAction::Momentary("media".to_owned())),
+    )
+    .unwrap();
+    hkm.register_hotkey(Hotkey::new(VKey::Space, [], tap(VKey::MediaPlayPause)).layer("media"))
+        .unwrap();
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    event_loop_thread.join().unwrap();
+}
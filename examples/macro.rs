@@ -0,0 +1,25 @@
+use win_hotkeys::macros::MacroPlayer;
+use win_hotkeys::{Hotkey, HotkeyManager, VKey};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+
+    // CTRL + F9 starts recording, CTRL + F10 stops it and replays the macro twice as fast
+    hkm.register_hotkey(Hotkey::new(VKey::F9, [VKey::Control], || {
+        println!("Recording...");
+        HotkeyManager::current().start_macro_recording();
+    }))
+    .unwrap();
+
+    hkm.register_hotkey(Hotkey::new(VKey::F10, [VKey::Control], || {
+        let recorded = HotkeyManager::current().stop_macro_recording();
+        println!("Replaying {} steps", recorded.steps.len());
+
+        let player = MacroPlayer::default().speed(2.0).release_modifiers_first();
+        player.play(&recorded).unwrap().join().unwrap();
+    }))
+    .unwrap();
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    event_loop_thread.join().unwrap();
+}
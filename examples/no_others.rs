@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use win_hotkeys::{Hotkey, HotkeyManager, TriggerTiming, VKey};
 
 fn main() {
@@ -13,13 +15,21 @@ fn main() {
     )
     .unwrap();
 
-    // If another key is pressed before the hotkey is triggered, the hotkey will not be triggered
+    // Triggered when WIN is pressed and released alone, any other key or a mouse click
+    // while WIN is held cancels it. The Start menu is not opened.
+    hkm.register_hotkey(Hotkey::modifier_tap(VKey::LWin, || {
+        println!("WIN pressed alone");
+    }))
+    .unwrap();
+
+    // Same for CTRL, but only if released within 200ms
     hkm.register_hotkey(
-        Hotkey::new(VKey::LWin, [], || {
-            println!("WIN pressed on a strict sequence");
+        Hotkey::modifier_tap(VKey::Control, || {
+            println!("CTRL tapped");
         })
-        .trigger_timing(TriggerTiming::OnKeyUp)
-        .strict_sequence(),
+        .trigger_timing(TriggerTiming::ModifierTap {
+            max: Some(Duration::from_millis(200)),
+        }),
     )
     .unwrap();
 
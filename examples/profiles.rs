@@ -0,0 +1,42 @@
+use win_hotkeys::{Hotkey, HotkeyManager, HotkeyProfile, VKey};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+
+    hkm.add_profile(
+        HotkeyProfile::new("editing")
+            .hotkey(Hotkey::new(VKey::D, [VKey::Control, VKey::Shift], || {
+                println!("Duplicate line");
+            }))
+            .hotkey(Hotkey::new(VKey::K, [VKey::Control, VKey::Shift], || {
+                println!("Delete line");
+            })),
+    )
+    .unwrap();
+    hkm.add_profile(
+        HotkeyProfile::new("gaming").hotkey(Hotkey::new(VKey::F9, [], || {
+            println!("Screenshot");
+        })),
+    )
+    .unwrap();
+    hkm.activate_profile("editing").unwrap();
+
+    // global hotkeys stay active whatever the profile
+    let profiles = hkm.clone();
+    hkm.register_hotkey(Hotkey::new(
+        VKey::P,
+        [VKey::Control, VKey::Menu],
+        move || {
+            let next = match profiles.active_profile().as_deref() {
+                Some("editing") => "gaming",
+                _ => "editing",
+            };
+            profiles.activate_profile(next).unwrap();
+            println!("Profile {next} active");
+        },
+    ))
+    .unwrap();
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    event_loop_thread.join().unwrap();
+}
@@ -0,0 +1,19 @@
+use win_hotkeys::{Hotkey, HotkeyManager, VKey};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+
+    // The release callback runs once for each press, as soon as CTRL or T is released
+    hkm.register_hotkey(
+        Hotkey::new(VKey::T, [VKey::Control], || {
+            println!("Microphone on");
+        })
+        .on_release(|| {
+            println!("Microphone off");
+        }),
+    )
+    .unwrap();
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    event_loop_thread.join().unwrap();
+}
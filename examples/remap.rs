@@ -0,0 +1,18 @@
+use win_hotkeys::{Hotkey, HotkeyManager, VKey};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+
+    // CAPSLOCK behaves as ESCAPE, and the swapped keys keep working in hotkeys
+    hkm.set_remap(VKey::Capital, VKey::Escape).unwrap();
+    hkm.set_remap(VKey::LMenu, VKey::LWin).unwrap();
+    hkm.set_remap(VKey::LWin, VKey::LMenu).unwrap();
+
+    hkm.register_hotkey(Hotkey::new(VKey::Escape, [VKey::Shift], || {
+        println!("SHIFT + CAPSLOCK pressed");
+    }))
+    .unwrap();
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    event_loop_thread.join().unwrap();
+}
@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use win_hotkeys::{Hotkey, HotkeyManager, VKey};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+    let initial_delay = Duration::from_millis(400);
+    let interval = Duration::from_millis(80);
+
+    // Fires on press, then keeps firing at its own rate while the combination is held,
+    // whatever the keyboard auto-repeat settings are
+    hkm.register_hotkey(
+        Hotkey::with_context(VKey::Up, [VKey::Control, VKey::Menu], |event| {
+            println!("Volume up (repeat {})", event.repeat);
+        })
+        .repeat_while_held(initial_delay, interval),
+    )
+    .unwrap();
+
+    hkm.register_hotkey(
+        Hotkey::with_context(VKey::Down, [VKey::Control, VKey::Menu], |event| {
+            println!("Volume down (repeat {})", event.repeat);
+        })
+        .repeat_while_held(initial_delay, interval),
+    )
+    .unwrap();
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    event_loop_thread.join().unwrap();
+}
@@ -0,0 +1,26 @@
+use win_hotkeys::events::HotkeyEvent;
+use win_hotkeys::{Hotkey, HotkeyManager, VKey};
+
+fn switch_workspace(event: &HotkeyEvent) {
+    println!(
+        "Hotkey {} fired by {:?} (repeat {}), pressed keys: {:?}",
+        event.id, event.trigger_key, event.repeat, event.state.pressing
+    );
+}
+
+fn main() {
+    let hkm = HotkeyManager::current();
+
+    // The same handler is shared by several hotkeys, the context tells which one fired
+    for key in [VKey::Digit1, VKey::Digit2, VKey::Digit3] {
+        hkm.register_hotkey(Hotkey::with_context(
+            key,
+            [VKey::LWin, VKey::Menu],
+            switch_workspace,
+        ))
+        .unwrap();
+    }
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    event_loop_thread.join().unwrap();
+}
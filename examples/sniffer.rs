@@ -1,48 +1,29 @@
-use std::sync::{Arc, LazyLock, Mutex};
+use std::thread;
+use std::time::Duration;
 
-use win_hotkeys::{events::KeyboardInputEvent, Hotkey, HotkeyManager, VKey};
-
-static LATEST_PRESSED: LazyLock<Arc<Mutex<Vec<VKey>>>> =
-    LazyLock::new(|| Arc::new(Mutex::new(Vec::new())));
+use win_hotkeys::{Hotkey, HotkeyManager, VKey};
 
 fn main() {
     let hkm = HotkeyManager::current();
 
     hkm.register_hotkey(Hotkey::new(VKey::P, [VKey::Control, VKey::Alt], || {
         println!("Hotkey CTRL + ALT + P was triggered");
-        let m = HotkeyManager::current();
-
-        println!("Starting stealing mode");
-        m.steal_keyboard(|| {
-            println!("Stealing mode canceled");
-            // remove listener as is un-neded
-            HotkeyManager::current().remove_global_keyboard_listener();
-            LATEST_PRESSED.lock().unwrap().clear(); // clean state
-        });
-
-        m.set_global_keyboard_listener(|event| {
-            if let KeyboardInputEvent::KeyDown {
-                key,
-                state: keyboard_state,
-            } = event
-            {
-                println!("Global key down: {key:?}");
-
-                if key == VKey::Return {
-                    println!("Enter pressed, stealing mode finished");
-                    let last = LATEST_PRESSED.lock().unwrap();
-
-                    println!("Registering new shortcut: {last:#?}");
-                    let shortcut = Hotkey::from_keys(last.as_slice());
-                    let manager = HotkeyManager::current();
+        println!("Press your new shortcut, ESC to cancel, BACKSPACE to start over");
+
+        // recording waits for the next key presses, it can't block a hotkey callback
+        thread::spawn(|| {
+            let manager = HotkeyManager::current();
+            match manager.record_shortcut(Duration::from_secs(10)) {
+                Ok(spec) => {
+                    println!("Registering new shortcut: {spec:#?}");
+                    let shortcut = Hotkey::new(spec.trigger_key, &spec.modifiers, || {
+                        println!("New shortcut was triggered");
+                    });
                     if let Err(e) = manager.register_hotkey(shortcut) {
                         println!("Failed to register shortcut: {e}");
                     }
-
-                    manager.free_keyboard(); // end stealing mode
-                } else {
-                    *LATEST_PRESSED.lock().unwrap() = keyboard_state.pressing;
                 }
+                Err(e) => println!("No shortcut recorded: {e}"),
             }
         });
     }))
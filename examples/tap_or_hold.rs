@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use win_hotkeys::{Hotkey, HotkeyManager, TriggerTiming, VKey};
+
+fn main() {
+    let hkm = HotkeyManager::current();
+    let limit = Duration::from_millis(300);
+
+    // CapsLock tapped: the key is consumed by the hotkey
+    hkm.register_hotkey(
+        Hotkey::new(VKey::Capital, [], || {
+            println!("CapsLock tapped");
+        })
+        .trigger_timing(TriggerTiming::Tap { max: limit }),
+    )
+    .unwrap();
+
+    // CapsLock held for more than 300ms
+    hkm.register_hotkey(
+        Hotkey::new(VKey::Capital, [], || {
+            println!("CapsLock held");
+        })
+        .trigger_timing(TriggerTiming::OnHold(limit)),
+    )
+    .unwrap();
+
+    // Only fires once CTRL + Q has been held for a second, a quick press of
+    // CTRL + Q is sent again to the focused application
+    hkm.register_hotkey(
+        Hotkey::new(VKey::Q, [VKey::Control], || {
+            println!("CTRL + Q held for a second");
+        })
+        .trigger_timing(TriggerTiming::OnHold(Duration::from_secs(1))),
+    )
+    .unwrap();
+
+    let event_loop_thread = HotkeyManager::start_keyboard_capturing().unwrap();
+    event_loop_thread.join().unwrap();
+}
@@ -0,0 +1,160 @@
+//! Tracking of the activations of hotkeys with a release callback, so that each
+//! press is paired with exactly one release, see [`Hotkey::on_release`].
+//!
+//! [`Hotkey::on_release`]: crate::Hotkey::on_release
+
+use crate::hotkey::HotkeyId;
+use crate::state::KeyboardState;
+use crate::VKey;
+
+#[derive(Debug)]
+struct Activation<T> {
+    id: HotkeyId,
+    /// keys that must stay down (trigger and modifiers)
+    combo: Vec<VKey>,
+    bypass_pause: bool,
+    release: T,
+}
+
+/// The pressed hotkeys waiting for their release.
+#[derive(Debug)]
+pub(crate) struct Activations<T> {
+    active: Vec<Activation<T>>,
+}
+
+impl<T> Activations<T> {
+    pub const fn new() -> Self {
+        Self { active: Vec::new() }
+    }
+
+    /// Registers a press of the hotkey `id`, returns the release of its previous
+    /// activation if it was still active.
+    pub fn activate(
+        &mut self,
+        id: HotkeyId,
+        combo: Vec<VKey>,
+        bypass_pause: bool,
+        release: T,
+    ) -> Option<T> {
+        let previous = self.take(|activation| activation.id == id).pop();
+        self.active.push(Activation {
+            id,
+            combo,
+            bypass_pause,
+            release,
+        });
+        previous
+    }
+
+    /// Removes and returns the releases of the activations whose combination is
+    /// no longer held in `state`.
+    pub fn released(&mut self, state: &KeyboardState) -> Vec<T> {
+        self.take(|activation| !activation.combo.iter().all(|key| state.is_down(*key)))
+    }
+
+    /// Removes and returns the releases of the activations stopped by a pause.
+    pub fn paused(&mut self) -> Vec<T> {
+        self.take(|activation| !activation.bypass_pause)
+    }
+
+    /// Removes and returns the releases of the activations of the hotkeys `ids`.
+    pub fn released_hotkeys(&mut self, ids: &[HotkeyId]) -> Vec<T> {
+        self.take(|activation| ids.contains(&activation.id))
+    }
+
+    /// Removes and returns all the releases.
+    pub fn release_all(&mut self) -> Vec<T> {
+        self.take(|_| true)
+    }
+
+    fn take<F: Fn(&Activation<T>) -> bool>(&mut self, predicate: F) -> Vec<T> {
+        let (taken, kept) = std::mem::take(&mut self.active)
+            .into_iter()
+            .partition::<Vec<_>, _>(|activation| predicate(activation));
+        self.active = kept;
+        taken
+            .into_iter()
+            .map(|activation| activation.release)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(keys: &[VKey]) -> KeyboardState {
+        let mut state = KeyboardState::new();
+        for key in keys {
+            state.keydown(*key);
+        }
+        state
+    }
+
+    fn push_to_talk() -> Activations<&'static str> {
+        let mut activations = Activations::new();
+        activations.activate(
+            HotkeyId::from(1),
+            vec![VKey::T, VKey::Control],
+            false,
+            "talk",
+        );
+        activations
+    }
+
+    #[test]
+    fn test_release_of_trigger() {
+        let mut activations = push_to_talk();
+
+        assert!(activations
+            .released(&state(&[VKey::LControl, VKey::T]))
+            .is_empty());
+        assert_eq!(
+            activations.released(&state(&[VKey::LControl])),
+            vec!["talk"]
+        );
+        assert!(
+            activations.released(&state(&[])).is_empty(),
+            "Released exactly once"
+        );
+    }
+
+    #[test]
+    fn test_release_of_modifier_first() {
+        let mut activations = push_to_talk();
+        assert_eq!(activations.released(&state(&[VKey::T])), vec!["talk"]);
+    }
+
+    #[test]
+    fn test_other_keys_do_not_release() {
+        let mut activations = push_to_talk();
+        assert!(activations
+            .released(&state(&[VKey::RControl, VKey::T, VKey::A]))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_pause_releases_unless_bypassed() {
+        let mut activations = push_to_talk();
+        activations.activate(HotkeyId::from(2), vec![VKey::M], true, "mute");
+
+        assert_eq!(activations.paused(), vec!["talk"]);
+        assert_eq!(activations.release_all(), vec!["mute"]);
+        assert!(activations.release_all().is_empty());
+    }
+
+    #[test]
+    fn test_reactivation_releases_previous() {
+        let mut activations = push_to_talk();
+        assert_eq!(
+            activations.activate(
+                HotkeyId::from(1),
+                vec![VKey::T, VKey::Control],
+                false,
+                "again"
+            ),
+            Some("talk")
+        );
+        assert_eq!(activations.release_all(), vec!["again"]);
+    }
+}
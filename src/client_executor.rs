@@ -1,8 +1,13 @@
 use std::sync::{Arc, LazyLock};
+use std::time::Duration;
 
 use crossbeam_channel::{Receiver, Sender};
 
+use crate::events::HotkeyEvent;
+use crate::hotkey::HotkeyCallback;
+use crate::layer::LayerSwitch;
 use crate::log_on_dev;
+use crate::usage::Usage;
 
 static CLIENT_CHANNEL: LazyLock<(Sender<ClientAction>, Receiver<ClientAction>)> =
     LazyLock::new(crossbeam_channel::unbounded);
@@ -11,9 +16,23 @@ static CLIENT_CHANNEL: LazyLock<(Sender<ClientAction>, Receiver<ClientAction>)>
 /// on a separated thead to avoid deadlocks.
 enum ClientAction {
     Call(Arc<dyn Fn() + Send + Sync>),
+    Hotkey(HotkeyCall),
     Stop,
 }
 
+/// A hotkey callback along with the context it will receive.
+#[derive(Clone)]
+pub(crate) struct HotkeyCall {
+    pub callback: HotkeyCallback,
+    /// cooldown of the hotkey, checked right before running the callback
+    pub cooldown: Duration,
+    /// uses left of the hotkey, consumed right before running the callback
+    pub usage: Arc<Usage>,
+    /// change of the active layers, applied in the event loop when the hotkey fires
+    pub layer_switch: Option<LayerSwitch>,
+    pub event: HotkeyEvent,
+}
+
 impl ClientAction {
     fn emit(self) {
         if CLIENT_CHANNEL.0.send(self).is_err() {
@@ -31,6 +50,7 @@ pub(crate) fn start_executor_thread() {
         while let Ok(event) = ClientAction::reciever().recv() {
             match event {
                 ClientAction::Call(cb) => cb(),
+                ClientAction::Hotkey(call) => (call.callback)(&call.event),
                 ClientAction::Stop => break,
             }
         }
@@ -41,6 +61,10 @@ pub(crate) fn run_on_executor_thread<F: Fn() + Send + Sync + 'static>(cb: Arc<F>
     ClientAction::Call(cb).emit();
 }
 
+pub(crate) fn run_hotkey_on_executor_thread(call: HotkeyCall) {
+    ClientAction::Hotkey(call).emit();
+}
+
 pub(crate) fn stop_executor_thread() {
     ClientAction::Stop.emit();
 }
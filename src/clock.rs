@@ -0,0 +1,76 @@
+//! Time source used by the timing based triggers (taps, holds, cooldowns...).
+//!
+//! The event loop never calls [`Instant::now`] directly, it asks the installed
+//! [`Clock`] instead. This allows tests to drive the timing logic with a
+//! [`ManualClock`] instead of real sleeps.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use arc_swap::ArcSwapOption;
+
+static CLOCK: ArcSwapOption<Box<dyn Clock>> = ArcSwapOption::const_empty();
+
+/// A source of monotonic time.
+pub trait Clock: Send + Sync {
+    /// Returns the current instant.
+    fn now(&self) -> Instant;
+}
+
+/// The default clock, backed by [`Instant::now`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when told to, intended for tests.
+///
+/// Clones share the same time, so a clone can be installed on the manager
+/// while the test keeps advancing the original.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<Instant>>,
+}
+
+impl ManualClock {
+    /// Creates a new `ManualClock` starting at the current instant.
+    pub fn new() -> Self {
+        Self {
+            now: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap()
+    }
+}
+
+/// Installs the clock used by the event loop.
+pub(crate) fn set<C: Clock + 'static>(clock: C) {
+    CLOCK.store(Some(Arc::new(Box::new(clock))));
+}
+
+/// Returns the current instant according to the installed clock.
+pub(crate) fn now() -> Instant {
+    match CLOCK.load().as_ref() {
+        Some(clock) => clock.now(),
+        None => Instant::now(),
+    }
+}
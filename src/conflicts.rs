@@ -0,0 +1,279 @@
+//! Static analysis of a set of hotkeys, reporting the bindings that can never
+//! trigger or that collide with each other.
+//!
+//! The analysis works on [`HotkeySpec`]s, a callback free description of a hotkey,
+//! so a settings UI can validate bindings before registering them.
+
+use std::cmp::Ordering;
+
+use crate::group::GroupId;
+use crate::hotkey::{
+    Hotkey, HotkeyId, HotkeyMetadata, ModifierMatch, TriggerBehavior, TriggerTiming,
+};
+use crate::layer::LayerAction;
+use crate::state::KeyboardState;
+use crate::{HotkeyManager, MatchPolicy, VKey};
+
+/// Describes a hotkey binding without its callback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HotkeySpec {
+    pub id: Option<HotkeyId>,
+    pub metadata: HotkeyMetadata,
+    pub trigger_key: VKey,
+    pub modifiers: Vec<VKey>,
+    pub trigger_timing: TriggerTiming,
+    pub modifier_match: ModifierMatch,
+    pub behaviour: TriggerBehavior,
+    pub bypass_pause: bool,
+    pub enabled: bool,
+    pub layer: Option<String>,
+    pub layer_action: Option<LayerAction>,
+    pub group: Option<GroupId>,
+    pub strict_sequence: bool,
+    pub taps: u8,
+    pub priority: i32,
+}
+
+impl HotkeySpec {
+    /// Creates a spec with the default settings of [`Hotkey::new`].
+    pub fn new<M: AsRef<[VKey]>>(trigger_key: VKey, modifiers: M) -> HotkeySpec {
+        HotkeySpec::from(&Hotkey::new(trigger_key, modifiers, || {}))
+    }
+
+    /// Builds a hotkey with a no-op callback, to reuse the matching logic.
+    fn to_hotkey(&self) -> Hotkey {
+        let mut hotkey = Hotkey::new(self.trigger_key, &self.modifiers, || {});
+        hotkey.id = self.id;
+        hotkey.metadata = self.metadata.clone();
+        hotkey.trigger_timing = self.trigger_timing;
+        hotkey.modifier_match = self.modifier_match;
+        hotkey.behaviour = self.behaviour;
+        hotkey.bypass_pause = self.bypass_pause;
+        hotkey.enabled = self.enabled;
+        hotkey.layer = self.layer.clone();
+        hotkey.layer_action = self.layer_action.clone();
+        hotkey.group = self.group;
+        hotkey.strict_sequence = self.strict_sequence;
+        hotkey.taps = self.taps;
+        hotkey.priority = self.priority;
+        hotkey
+    }
+}
+
+impl From<&Hotkey> for HotkeySpec {
+    fn from(hotkey: &Hotkey) -> Self {
+        HotkeySpec {
+            id: hotkey.id,
+            metadata: hotkey.metadata.clone(),
+            trigger_key: hotkey.trigger_key,
+            modifiers: hotkey.modifiers.clone(),
+            trigger_timing: hotkey.trigger_timing,
+            modifier_match: hotkey.modifier_match,
+            behaviour: hotkey.behaviour,
+            bypass_pause: hotkey.bypass_pause,
+            enabled: hotkey.enabled,
+            layer: hotkey.layer.clone(),
+            layer_action: hotkey.layer_action.clone(),
+            group: hotkey.group,
+            strict_sequence: hotkey.strict_sequence,
+            taps: hotkey.taps,
+            priority: hotkey.priority,
+        }
+    }
+}
+
+/// A problem found between hotkey bindings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Conflict {
+    /// Both hotkeys are the same binding, registering the second one fails
+    Duplicate {
+        hotkey: HotkeySpec,
+        other: HotkeySpec,
+    },
+    /// Pressing the combination of `hotkey` triggers `by` instead, see [`MatchPolicy`]
+    Shadowed { hotkey: HotkeySpec, by: HotkeySpec },
+    /// The hold or tap `hotkey` never triggers, as the key down event of its
+    /// combination is always handled by the `OnKeyDown` hotkey `by`
+    Unreachable { hotkey: HotkeySpec, by: HotkeySpec },
+    /// The hotkey overlaps a system shortcut that can't be overridden
+    ReservedShortcut {
+        hotkey: HotkeySpec,
+        shortcut: HotkeySpec,
+    },
+}
+
+/// Finds the conflicts between the given hotkeys, listed in registration order.
+///
+/// Shadowing only exists with [`MatchPolicy::FirstMatch`], as every matching
+/// hotkey runs with [`MatchPolicy::AllMatches`]. Disabled hotkeys are ignored, and
+/// hotkeys of different layers never conflict, as the active layers decide which
+/// one triggers.
+pub fn analyze_conflicts(specs: &[HotkeySpec], policy: MatchPolicy) -> Vec<Conflict> {
+    analyze(specs, policy, false)
+}
+
+/// Finds the conflicts between the given hotkeys like [`analyze_conflicts`],
+/// including the disabled hotkeys as if they were enabled.
+pub fn analyze_conflicts_including_disabled(
+    specs: &[HotkeySpec],
+    policy: MatchPolicy,
+) -> Vec<Conflict> {
+    analyze(specs, policy, true)
+}
+
+fn analyze(specs: &[HotkeySpec], policy: MatchPolicy, include_disabled: bool) -> Vec<Conflict> {
+    let hotkeys: Vec<Hotkey> = specs.iter().map(HotkeySpec::to_hotkey).collect();
+    let reserved = reserved_shortcuts();
+    let mut conflicts = Vec::new();
+
+    let ignored = |hotkey: &Hotkey| !include_disabled && !hotkey.enabled;
+    for (i, hotkey) in hotkeys.iter().enumerate() {
+        if ignored(hotkey) {
+            continue;
+        }
+        for shortcut in &reserved {
+            if overlaps_reserved(&specs[i], shortcut) {
+                conflicts.push(Conflict::ReservedShortcut {
+                    hotkey: specs[i].clone(),
+                    shortcut: HotkeySpec::from(shortcut),
+                });
+            }
+        }
+
+        for (j, other) in hotkeys.iter().enumerate() {
+            if i == j
+                || hotkey.trigger_key != other.trigger_key
+                || hotkey.layer != other.layer
+                || ignored(other)
+            {
+                continue;
+            }
+
+            if hotkey == other {
+                if i < j {
+                    conflicts.push(Conflict::Duplicate {
+                        hotkey: specs[i].clone(),
+                        other: specs[j].clone(),
+                    });
+                }
+                continue;
+            }
+
+            let triggers_first = triggers_on_press_of(other, hotkey);
+            if is_press_bound(hotkey)
+                && other.trigger_timing == TriggerTiming::OnKeyDown
+                && triggers_first
+            {
+                conflicts.push(Conflict::Unreachable {
+                    hotkey: specs[i].clone(),
+                    by: specs[j].clone(),
+                });
+                continue;
+            }
+
+            let wins = match other.cmp_precedence(hotkey) {
+                Ordering::Less => true,
+                Ordering::Equal => j < i,
+                Ordering::Greater => false,
+            };
+            // all the hold and tap hotkeys of a press are tracked together
+            if policy == MatchPolicy::FirstMatch
+                && !is_press_bound(hotkey)
+                && hotkey.trigger_timing == other.trigger_timing
+                && hotkey.taps == other.taps
+                && wins
+                && triggers_first
+            {
+                conflicts.push(Conflict::Shadowed {
+                    hotkey: specs[i].clone(),
+                    by: specs[j].clone(),
+                });
+            }
+        }
+    }
+    conflicts
+}
+
+impl HotkeyManager {
+    /// Finds the conflicts between the enabled hotkeys, see [`analyze_conflicts`].
+    pub fn analyze_conflicts(&self) -> Vec<Conflict> {
+        analyze(&self.list_hotkeys(), self.match_policy(), false)
+    }
+
+    /// Finds the conflicts between the registered hotkeys, including the disabled ones.
+    pub fn analyze_conflicts_including_disabled(&self) -> Vec<Conflict> {
+        analyze(&self.list_hotkeys(), self.match_policy(), true)
+    }
+
+    /// Lists the registered hotkeys, grouped by trigger key and in registration order,
+    /// without the system shortcuts registered by default.
+    pub fn list_hotkeys(&self) -> Vec<HotkeySpec> {
+        let reserved = reserved_shortcuts();
+        self.hotkeys
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|hotkey| !reserved.contains(hotkey))
+            .map(HotkeySpec::from)
+            .collect()
+    }
+}
+
+/// The system shortcuts registered by default on the manager.
+fn reserved_shortcuts() -> Vec<Hotkey> {
+    HotkeyManager::get_initial_hotkeys()
+}
+
+/// Returns the first of the system shortcuts and `extra` overlapping `spec`.
+pub(crate) fn reserved_overlap(spec: &HotkeySpec, extra: &[HotkeySpec]) -> Option<HotkeySpec> {
+    reserved_shortcuts()
+        .iter()
+        .map(HotkeySpec::from)
+        .chain(extra.iter().cloned())
+        .find(|shortcut| overlaps_reserved(spec, &shortcut.to_hotkey()))
+}
+
+/// Hold and tap hotkeys are only considered when no `OnKeyDown` hotkey matches.
+fn is_press_bound(hotkey: &Hotkey) -> bool {
+    matches!(
+        hotkey.trigger_timing,
+        TriggerTiming::OnHold(_) | TriggerTiming::Tap { .. }
+    )
+}
+
+/// Checks whether `hotkey` matches the keyboard state produced by pressing the
+/// combination of `other`, in its declared order.
+fn triggers_on_press_of(hotkey: &Hotkey, other: &Hotkey) -> bool {
+    let mut state = KeyboardState::new();
+    for key in &other.modifiers {
+        state.keydown(concrete(*key));
+    }
+    state.keydown(other.trigger_key);
+    if hotkey.trigger_timing == TriggerTiming::OnKeyUp {
+        state.keyup(other.trigger_key);
+    }
+    hotkey.is_trigger_state(&other.trigger_key, &state)
+}
+
+/// A reserved shortcut is overlapped if either combination triggers the other,
+/// whatever the timing of the hotkey.
+fn overlaps_reserved(spec: &HotkeySpec, shortcut: &Hotkey) -> bool {
+    if spec.trigger_key != shortcut.trigger_key {
+        return false;
+    }
+    let mut hotkey = spec.to_hotkey();
+    hotkey.trigger_timing = TriggerTiming::OnKeyDown;
+    triggers_on_press_of(&hotkey, shortcut) || triggers_on_press_of(shortcut, &hotkey)
+}
+
+/// Generic modifiers are pressed as their left variant.
+fn concrete(key: VKey) -> VKey {
+    match key {
+        VKey::Control => VKey::LControl,
+        VKey::Shift => VKey::LShift,
+        VKey::Menu => VKey::LMenu,
+        key => key,
+    }
+}
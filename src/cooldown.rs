@@ -0,0 +1,117 @@
+//! Rate limiting of the hotkey callbacks: a cooldown per hotkey, and a global
+//! minimum interval between any two callback runs.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::hotkey::HotkeyId;
+
+#[derive(Debug)]
+pub(crate) struct CooldownTracker {
+    last_runs: Option<HashMap<HotkeyId, Instant>>,
+    last_run: Option<Instant>,
+    min_interval: Duration,
+}
+
+impl CooldownTracker {
+    pub const fn new() -> Self {
+        Self {
+            last_runs: None,
+            last_run: None,
+            min_interval: Duration::ZERO,
+        }
+    }
+
+    /// Sets the minimum time between any two callback runs.
+    pub fn set_min_interval(&mut self, interval: Duration) {
+        self.min_interval = interval;
+    }
+
+    /// Checks whether the hotkey `id` can run its callback at `now`.
+    pub fn is_ready(&self, id: HotkeyId, cooldown: Duration, now: Instant) -> bool {
+        let elapsed = |last: Option<&Instant>, interval: Duration| {
+            last.is_none_or(|last| now.saturating_duration_since(*last) >= interval)
+        };
+        let last_run = self.last_runs.as_ref().and_then(|runs| runs.get(&id));
+        elapsed(last_run, cooldown) && elapsed(self.last_run.as_ref(), self.min_interval)
+    }
+
+    /// Records a callback run if the hotkey is ready, returns whether it is.
+    pub fn try_run(&mut self, id: HotkeyId, cooldown: Duration, now: Instant) -> bool {
+        if !self.is_ready(id, cooldown, now) {
+            return false;
+        }
+        self.last_runs.get_or_insert_default().insert(id, now);
+        self.last_run = Some(now);
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.last_runs = None;
+        self.last_run = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    const COOLDOWN: Duration = Duration::from_millis(500);
+
+    #[test]
+    fn test_cooldown() {
+        let clock = ManualClock::new();
+        let mut tracker = CooldownTracker::new();
+        let id = HotkeyId::from(1);
+
+        assert!(tracker.try_run(id, COOLDOWN, clock.now()));
+        clock.advance(Duration::from_millis(100));
+        assert!(!tracker.try_run(id, COOLDOWN, clock.now()));
+
+        clock.advance(Duration::from_millis(400));
+        assert!(tracker.try_run(id, COOLDOWN, clock.now()));
+    }
+
+    #[test]
+    fn test_suppressed_runs_do_not_extend_cooldown() {
+        let clock = ManualClock::new();
+        let mut tracker = CooldownTracker::new();
+        let id = HotkeyId::from(1);
+
+        assert!(tracker.try_run(id, COOLDOWN, clock.now()));
+        for _ in 0..4 {
+            clock.advance(Duration::from_millis(100));
+            assert!(!tracker.try_run(id, COOLDOWN, clock.now()));
+        }
+        clock.advance(Duration::from_millis(100));
+        assert!(tracker.try_run(id, COOLDOWN, clock.now()));
+    }
+
+    #[test]
+    fn test_cooldowns_are_per_hotkey() {
+        let clock = ManualClock::new();
+        let mut tracker = CooldownTracker::new();
+
+        assert!(tracker.try_run(HotkeyId::from(1), COOLDOWN, clock.now()));
+        assert!(tracker.try_run(HotkeyId::from(2), COOLDOWN, clock.now()));
+        assert!(
+            tracker.try_run(HotkeyId::from(3), Duration::ZERO, clock.now()),
+            "No cooldown, no global interval"
+        );
+    }
+
+    #[test]
+    fn test_global_min_interval() {
+        let clock = ManualClock::new();
+        let mut tracker = CooldownTracker::new();
+        tracker.set_min_interval(Duration::from_millis(50));
+
+        assert!(tracker.try_run(HotkeyId::from(1), Duration::ZERO, clock.now()));
+        clock.advance(Duration::from_millis(20));
+        assert!(!tracker.try_run(HotkeyId::from(2), Duration::ZERO, clock.now()));
+
+        clock.advance(Duration::from_millis(30));
+        assert!(tracker.try_run(HotkeyId::from(2), Duration::ZERO, clock.now()));
+    }
+}
@@ -0,0 +1,208 @@
+//! Synchronous decision callbacks, deciding at runtime whether a matched hotkey
+//! handles the key event (and blocks it) or lets it through.
+//!
+//! The key event can't be released to the hook until the decision is taken, so
+//! the callback runs within a time budget. It is evaluated on a dedicated thread
+//! while the event loop waits for it, which allows the budget to be enforced
+//! even if the callback hangs. A callback still running when the next decision
+//! is requested doesn't delay it, the next one runs on a thread of its own.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use crossbeam_channel::Sender;
+
+use crate::events::{HotkeyEvent, KeyAction};
+use crate::hook;
+use crate::log_on_dev;
+
+pub(crate) type DecisionCallback = Arc<dyn Fn(&HotkeyEvent) -> KeyAction + Send + Sync + 'static>;
+
+/// Default time budget of a decision callback.
+pub(crate) const DEFAULT_DECISION_BUDGET: Duration = Duration::from_millis(50);
+
+/// Maximum time budget of a decision callback, the hook lets the key through
+/// if the event loop doesn't answer in time, whatever the decision.
+pub(crate) const MAX_DECISION_BUDGET: Duration =
+    hook::TIMEOUT.saturating_sub(Duration::from_millis(50));
+
+struct DecisionRequest {
+    callback: DecisionCallback,
+    event: HotkeyEvent,
+    fallback: KeyAction,
+    reply: Sender<KeyAction>,
+}
+
+impl DecisionRequest {
+    fn run(self) {
+        let action = catch_unwind(AssertUnwindSafe(|| (self.callback)(&self.event)))
+            .unwrap_or(self.fallback);
+        // the event loop may have given up waiting, nothing to do in that case
+        let _ = self.reply.send(action);
+    }
+}
+
+/// Whether the worker is evaluating a callback.
+static WORKER_BUSY: AtomicBool = AtomicBool::new(false);
+
+static DECISION_WORKER: LazyLock<Sender<DecisionRequest>> = LazyLock::new(|| {
+    let (tx, rx) = crossbeam_channel::unbounded::<DecisionRequest>();
+    std::thread::spawn(move || {
+        while let Ok(request) = rx.recv() {
+            request.run();
+            WORKER_BUSY.store(false, Ordering::Release);
+        }
+    });
+    tx
+});
+
+/// Evaluates the decision callback, returns `fallback` if it doesn't answer within
+/// `budget`, which is clamped to [`MAX_DECISION_BUDGET`].
+pub(crate) fn decide(
+    callback: &DecisionCallback,
+    event: HotkeyEvent,
+    budget: Duration,
+    fallback: KeyAction,
+) -> KeyAction {
+    let budget = budget.min(MAX_DECISION_BUDGET);
+    let (reply, response) = crossbeam_channel::bounded(1);
+    let request = DecisionRequest {
+        callback: callback.clone(),
+        event,
+        fallback,
+        reply,
+    };
+    if WORKER_BUSY.swap(true, Ordering::AcqRel) {
+        // a previous callback is still running, maybe hung, don't queue behind it
+        log_on_dev!("Decision worker busy, deciding on a new thread");
+        std::thread::spawn(move || request.run());
+    } else if DECISION_WORKER.send(request).is_err() {
+        return fallback;
+    }
+    match response.recv_timeout(budget) {
+        Ok(action) => action,
+        Err(_) => {
+            log_on_dev!("Decision callback exceeded its budget of {budget:?}");
+            fallback
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Hotkey;
+    use crate::VKey;
+
+    fn event() -> HotkeyEvent {
+        Hotkey::new(VKey::V, [VKey::Control], || {}).event(&Default::default(), 0)
+    }
+
+    #[test]
+    fn test_decision_result_is_returned() {
+        let block: DecisionCallback = Arc::new(|_| KeyAction::Block);
+        let allow: DecisionCallback = Arc::new(|_| KeyAction::Allow);
+        let budget = Duration::from_secs(1);
+
+        assert_eq!(
+            decide(&block, event(), budget, KeyAction::Allow),
+            KeyAction::Block
+        );
+        assert_eq!(
+            decide(&allow, event(), budget, KeyAction::Block),
+            KeyAction::Allow
+        );
+    }
+
+    #[test]
+    fn test_decision_receives_event() {
+        let callback: DecisionCallback = Arc::new(|event| {
+            if event.trigger_key == VKey::V {
+                KeyAction::Block
+            } else {
+                KeyAction::Allow
+            }
+        });
+        assert_eq!(
+            decide(&callback, event(), Duration::from_secs(1), KeyAction::Allow),
+            KeyAction::Block
+        );
+    }
+
+    #[test]
+    fn test_budget_exceeded_uses_fallback() {
+        let slow: DecisionCallback = Arc::new(|_| {
+            std::thread::sleep(Duration::from_millis(200));
+            KeyAction::Block
+        });
+        assert_eq!(
+            decide(&slow, event(), Duration::from_millis(10), KeyAction::Allow),
+            KeyAction::Allow
+        );
+    }
+
+    #[test]
+    fn test_hung_decision_does_not_delay_the_next_ones() {
+        let (release, hung) = crossbeam_channel::bounded::<()>(0);
+        let hanging: DecisionCallback = Arc::new(move |_| {
+            let _ = hung.recv();
+            KeyAction::Block
+        });
+        assert_eq!(
+            decide(
+                &hanging,
+                event(),
+                Duration::from_millis(10),
+                KeyAction::Allow
+            ),
+            KeyAction::Allow
+        );
+
+        let block: DecisionCallback = Arc::new(|_| KeyAction::Block);
+        for _ in 0..3 {
+            assert_eq!(
+                decide(&block, event(), Duration::from_secs(1), KeyAction::Allow),
+                KeyAction::Block
+            );
+        }
+        drop(release);
+    }
+
+    #[test]
+    fn test_budget_is_clamped_below_the_hook_timeout() {
+        assert!(MAX_DECISION_BUDGET < hook::TIMEOUT);
+        let slow: DecisionCallback = Arc::new(|_| {
+            std::thread::sleep(hook::TIMEOUT);
+            KeyAction::Block
+        });
+        let started = std::time::Instant::now();
+        assert_eq!(
+            decide(&slow, event(), Duration::from_secs(5), KeyAction::Allow),
+            KeyAction::Allow
+        );
+        assert!(started.elapsed() < hook::TIMEOUT);
+    }
+
+    #[test]
+    fn test_panicking_decision_uses_fallback() {
+        let panicking: DecisionCallback = Arc::new(|_| panic!("decision failed"));
+        assert_eq!(
+            decide(
+                &panicking,
+                event(),
+                Duration::from_secs(1),
+                KeyAction::Block
+            ),
+            KeyAction::Block
+        );
+
+        // the worker survives the panic
+        let allow: DecisionCallback = Arc::new(|_| KeyAction::Allow);
+        assert_eq!(
+            decide(&allow, event(), Duration::from_secs(1), KeyAction::Block),
+            KeyAction::Allow
+        );
+    }
+}
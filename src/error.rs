@@ -4,7 +4,7 @@
 
 use thiserror::Error;
 
-use crate::VKey;
+use crate::{HotkeyId, HotkeySpec, VKey};
 
 /// An enumeration of errors that may occur while using the crate.
 #[derive(Error, Debug)]
@@ -15,10 +15,26 @@ pub enum WHKError {
     StartupFailed,
     #[error("Hotkey registration failed. Hotkey is already in use.")]
     HotKeyAlreadyRegistered,
+    #[error("Hotkey id `{0}` is already in use.")]
+    HotkeyIdInUse(HotkeyId),
+    #[error("No hotkey registered with id `{0}`.")]
+    HotkeyNotFound(HotkeyId),
     #[error("Invalid trigger key `{0:?}`")]
     HotkeyInvalidTriggerKey(VKey),
+    #[error("No hotkey profile named `{0}`.")]
+    ProfileNotFound(String),
+    #[error("Hotstring registration failed. Hotstring `{0}` is already in use.")]
+    HotstringAlreadyRegistered(String),
+    #[error("Invalid hotstring `{0}`")]
+    InvalidHotstring(String),
+    #[error("Invalid remap key `{0:?}`")]
+    InvalidRemap(VKey),
     #[error("Invalid key name `{0}`")]
     InvalidKey(String),
+    #[error("Invalid text template `{0}`")]
+    InvalidTemplate(String),
+    #[error("Input injection failed, it may be blocked by a higher integrity process.")]
+    InputInjectionFailed,
     // crossbeam
     #[error("Sending event failed")]
     SendFailed,
@@ -40,4 +56,19 @@ impl<T> From<std::sync::PoisonError<T>> for WHKError {
     }
 }
 
+/// Reasons why no shortcut was recorded, see [`crate::ShortcutRecorder`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ShortcutError {
+    #[error("Shortcut recording was cancelled.")]
+    Cancelled,
+    #[error("No shortcut was recorded before the timeout.")]
+    TimedOut,
+    #[error("Shortcut has no key other than modifiers.")]
+    ModifierOnly,
+    #[error("Shortcut overlaps the reserved shortcut `{0:?}`.")]
+    Reserved(Box<HotkeySpec>),
+    #[error("A shortcut is already being recorded, or the keyboard is stolen.")]
+    Busy,
+}
+
 pub type Result<T, E = WHKError> = std::result::Result<T, E>;
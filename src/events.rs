@@ -1,8 +1,9 @@
 use std::sync::LazyLock;
+use std::time::Instant;
 
 use crossbeam_channel::{Receiver, Sender};
 
-use crate::{log_on_dev, state::KeyboardState, VKey};
+use crate::{log_on_dev, state::KeyboardState, HotkeyId, TriggerTiming, VKey};
 
 static EVENT_LOOP_CHANNEL: LazyLock<(Sender<EventLoopEvent>, Receiver<EventLoopEvent>)> =
     LazyLock::new(crossbeam_channel::unbounded);
@@ -14,6 +15,12 @@ static ACTION_CHANNEL: LazyLock<(Sender<KeyAction>, Receiver<KeyAction>)> =
 pub enum EventLoopEvent {
     Stop,
     Keyboard(KeyboardInputEvent),
+    /// A mouse button was pressed, no key action is expected in response.
+    MouseButtonDown,
+    /// Another window came to the foreground, no key action is expected in response.
+    ForegroundChanged,
+    /// A timer was scheduled from another thread, the event loop recomputes its deadline.
+    TimersChanged,
 }
 
 impl EventLoopEvent {
@@ -48,11 +55,64 @@ pub enum KeyboardInputEvent {
     },
 }
 
+/// Context received by the hotkey callbacks, describing why the hotkey fired.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotkeyEvent {
+    /// The id of the hotkey that fired, as returned on registration.
+    pub id: HotkeyId,
+    /// The trigger key of the hotkey.
+    pub trigger_key: VKey,
+    /// The timing that triggered the hotkey.
+    pub timing: TriggerTiming,
+    /// The keyboard state that matched the hotkey.
+    pub state: KeyboardState,
+    /// When the hotkey fired.
+    pub timestamp: Instant,
+    /// How many times the trigger key was auto-repeated, `0` for a fresh press.
+    pub repeat: u32,
+}
+
+/// Counts the auto-repeated key down events of the last pressed key.
+#[derive(Debug, Default)]
+pub(crate) struct RepeatCounter {
+    key: Option<VKey>,
+    count: u32,
+}
+
+impl RepeatCounter {
+    /// Updates the counter with a new event and returns how many times the
+    /// key was repeated, `0` means a fresh press.
+    pub fn observe(&mut self, event: &KeyboardInputEvent) -> u32 {
+        match event {
+            KeyboardInputEvent::KeyDown { key, .. } => {
+                if self.key == Some(*key) {
+                    self.count += 1;
+                } else {
+                    self.key = Some(*key);
+                    self.count = 0;
+                }
+                self.count
+            }
+            KeyboardInputEvent::KeyUp { key, .. } => {
+                if self.key == Some(*key) {
+                    self.key = None;
+                    self.count = 0;
+                }
+                0
+            }
+        }
+    }
+}
+
 /// Enum representing how to handle keypress.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum KeyAction {
     Allow,
+    /// Blocks a key press, key releases still go through as blocking the release
+    /// of a key whose press went through leaves it stuck for other applications.
     Block,
+    /// Blocks the event, a key release included.
+    Suppress,
 }
 
 impl KeyAction {
@@ -0,0 +1,189 @@
+//! Groups of hotkeys managed together, e.g. the "media" bindings or the hotkeys
+//! of a plugin.
+//!
+//! Hotkeys registered through a [`HotkeyGroup`] handle belong to its group, which
+//! can be paused, disabled, listed or unregistered at once without touching the
+//! hotkeys of other groups.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::conflicts::HotkeySpec;
+use crate::error::{Result, WHKError};
+use crate::hotkey::{Hotkey, HotkeyId};
+use crate::registry::HotkeyRegistry;
+use crate::HotkeyManager;
+
+static NEXT_GROUP_ID: AtomicU64 = AtomicU64::new(1);
+pub(crate) static PAUSED_GROUPS: Mutex<Vec<GroupId>> = Mutex::new(Vec::new());
+
+/// Identifies a group of hotkeys.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GroupId(u64);
+
+impl GroupId {
+    /// Returns the numeric value of the id.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for GroupId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A handle registering hotkeys in a group, see [`HotkeyManager::create_group`].
+///
+/// The operations of the handle only concern the hotkeys of its group, so
+/// plugins given their own handle are isolated from each other.
+#[derive(Debug, Clone)]
+pub struct HotkeyGroup {
+    id: GroupId,
+    name: String,
+    manager: HotkeyManager,
+}
+
+impl HotkeyGroup {
+    pub fn id(&self) -> GroupId {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Registers a hotkey in the group, returns its id.
+    pub fn register_hotkey(&self, mut hotkey: Hotkey) -> Result<HotkeyId> {
+        hotkey.group = Some(self.id);
+        self.manager.register_hotkey(hotkey)
+    }
+
+    /// Unregisters a hotkey of the group, fails if it belongs to another group.
+    pub fn unregister_hotkey(&self, hotkey_id: HotkeyId) -> Result<()> {
+        let mut hotkeys = self.manager.hotkeys.lock()?;
+        match hotkeys.get(hotkey_id) {
+            Some(hotkey) if hotkey.group == Some(self.id) => {
+                hotkeys.unregister(hotkey_id);
+                Ok(())
+            }
+            _ => Err(WHKError::HotkeyNotFound(hotkey_id)),
+        }
+    }
+
+    /// Pauses the hotkeys of the group, see [`HotkeyManager::pause_group`].
+    pub fn pause(&self) {
+        self.manager.pause_group(self.id);
+    }
+
+    pub fn resume(&self) {
+        self.manager.resume_group(self.id);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.manager.is_group_paused(self.id)
+    }
+
+    /// Enables or disables all the hotkeys of the group.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.manager.set_group_enabled(self.id, enabled);
+    }
+
+    /// Unregisters all the hotkeys of the group.
+    pub fn unregister_all(&self) {
+        self.manager.unregister_group(self.id);
+    }
+
+    /// Lists the hotkeys of the group.
+    pub fn list_hotkeys(&self) -> Vec<HotkeySpec> {
+        self.manager.group_hotkeys(self.id)
+    }
+}
+
+impl HotkeyManager {
+    /// Creates a new group of hotkeys, `name` is only descriptive.
+    pub fn create_group<S: Into<String>>(&self, name: S) -> HotkeyGroup {
+        HotkeyGroup {
+            id: GroupId(NEXT_GROUP_ID.fetch_add(1, Ordering::Relaxed)),
+            name: name.into(),
+            manager: self.clone(),
+        }
+    }
+
+    /// Pauses the hotkeys of a group, whatever their [`Hotkey::bypass_pause`], and
+    /// releases the ones currently pressed.
+    pub fn pause_group(&self, group: GroupId) {
+        {
+            let mut paused = PAUSED_GROUPS.lock().unwrap();
+            if paused.contains(&group) {
+                return;
+            }
+            paused.push(group);
+        }
+        let ids = group_ids(&self.hotkeys.lock().unwrap(), group);
+        HotkeyManager::release_hotkeys(&ids);
+    }
+
+    pub fn resume_group(&self, group: GroupId) {
+        PAUSED_GROUPS
+            .lock()
+            .unwrap()
+            .retain(|paused| *paused != group);
+    }
+
+    pub fn is_group_paused(&self, group: GroupId) -> bool {
+        PAUSED_GROUPS.lock().unwrap().contains(&group)
+    }
+
+    /// Enables or disables all the hotkeys of a group, see [`HotkeyManager::set_enabled`].
+    pub fn set_group_enabled(&self, group: GroupId, enabled: bool) {
+        let ids = {
+            let mut hotkeys = self.hotkeys.lock().unwrap();
+            let ids = group_ids(&hotkeys, group);
+            for id in &ids {
+                if let Some(hotkey) = hotkeys.get_mut(*id) {
+                    hotkey.enabled = enabled;
+                }
+            }
+            ids
+        };
+        if !enabled {
+            HotkeyManager::release_hotkeys(&ids);
+        }
+    }
+
+    /// Unregisters all the hotkeys of a group, and releases the ones currently pressed.
+    pub fn unregister_group(&self, group: GroupId) {
+        let ids = {
+            let mut hotkeys = self.hotkeys.lock().unwrap();
+            let ids = group_ids(&hotkeys, group);
+            for id in &ids {
+                hotkeys.unregister(*id);
+            }
+            ids
+        };
+        HotkeyManager::release_hotkeys(&ids);
+    }
+
+    /// Lists the hotkeys of a group, grouped by trigger key and in registration order.
+    pub fn group_hotkeys(&self, group: GroupId) -> Vec<HotkeySpec> {
+        self.hotkeys
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|hotkey| hotkey.group == Some(group))
+            .map(HotkeySpec::from)
+            .collect()
+    }
+}
+
+fn group_ids(hotkeys: &HotkeyRegistry, group: GroupId) -> Vec<HotkeyId> {
+    hotkeys
+        .iter()
+        .filter(|hotkey| hotkey.group == Some(group))
+        .filter_map(|hotkey| hotkey.id)
+        .collect()
+}
@@ -0,0 +1,125 @@
+//! Tracking of the combinations held down for hotkeys that keep firing at their
+//! own rate, see [`Hotkey::repeat_while_held`].
+//!
+//! [`Hotkey::repeat_while_held`]: crate::Hotkey::repeat_while_held
+
+use std::time::{Duration, Instant};
+
+use crate::VKey;
+
+/// A combination held down, firing `action` on each repeat deadline.
+#[derive(Debug)]
+pub(crate) struct HeldRepeat<T> {
+    /// keys whose release stops the repeat (trigger and modifiers)
+    combo: Vec<VKey>,
+    interval: Duration,
+    next: Instant,
+    count: u32,
+    pub action: T,
+}
+
+impl<T> HeldRepeat<T> {
+    pub fn new(
+        combo: Vec<VKey>,
+        pressed: Instant,
+        initial_delay: Duration,
+        interval: Duration,
+        action: T,
+    ) -> Self {
+        Self {
+            combo,
+            interval,
+            next: pressed + initial_delay,
+            count: 0,
+            action,
+        }
+    }
+
+    /// Deadline of the next repeat.
+    pub fn deadline(&self) -> Instant {
+        self.next
+    }
+
+    /// Returns the number of the repeat due at `now`, if any, and moves to the next one.
+    ///
+    /// Missed deadlines are skipped, a late event loop doesn't fire a burst of repeats.
+    pub fn elapsed(&mut self, now: Instant) -> Option<u32> {
+        if now < self.next {
+            return None;
+        }
+        self.count += 1;
+        self.next += self.interval.max(Duration::from_millis(1));
+        while self.next <= now {
+            self.next += self.interval.max(Duration::from_millis(1));
+        }
+        Some(self.count)
+    }
+
+    /// Checks whether releasing `key` stops the repeat.
+    pub fn is_released_by(&self, key: VKey) -> bool {
+        self.combo.iter().any(|combo_key| combo_key.matches(&key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    const DELAY: Duration = Duration::from_millis(400);
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    fn volume_up(clock: &ManualClock) -> HeldRepeat<&'static str> {
+        HeldRepeat::new(
+            vec![VKey::Up, VKey::Control],
+            clock.now(),
+            DELAY,
+            INTERVAL,
+            "volume up",
+        )
+    }
+
+    #[test]
+    fn test_initial_delay_then_interval() {
+        let clock = ManualClock::new();
+        let mut repeat = volume_up(&clock);
+
+        clock.advance(Duration::from_millis(399));
+        assert_eq!(repeat.elapsed(clock.now()), None);
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(repeat.elapsed(clock.now()), Some(1));
+        assert_eq!(repeat.elapsed(clock.now()), None, "Fires once per deadline");
+
+        clock.advance(INTERVAL);
+        assert_eq!(repeat.elapsed(clock.now()), Some(2));
+        assert_eq!(repeat.deadline(), clock.now() + INTERVAL);
+    }
+
+    #[test]
+    fn test_late_event_loop_skips_missed_deadlines() {
+        let clock = ManualClock::new();
+        let mut repeat = volume_up(&clock);
+
+        clock.advance(DELAY + INTERVAL * 3 + Duration::from_millis(50));
+        assert_eq!(repeat.elapsed(clock.now()), Some(1));
+        assert_eq!(repeat.elapsed(clock.now()), None);
+        assert_eq!(
+            repeat.deadline(),
+            clock.now() + Duration::from_millis(50),
+            "Next deadline stays on the interval grid"
+        );
+    }
+
+    #[test]
+    fn test_released_by_any_combo_key() {
+        let clock = ManualClock::new();
+        let repeat = volume_up(&clock);
+
+        assert!(repeat.is_released_by(VKey::Up));
+        assert!(
+            repeat.is_released_by(VKey::RControl),
+            "Generic modifiers match either side"
+        );
+        assert!(!repeat.is_released_by(VKey::Shift));
+    }
+}
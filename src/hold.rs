@@ -0,0 +1,218 @@
+//! Tracking of presses bound to [`TriggerTiming::OnHold`] and [`TriggerTiming::Tap`]
+//! hotkeys, whose outcome is only known once the key is released or held long enough.
+//!
+//! [`TriggerTiming::OnHold`]: crate::TriggerTiming::OnHold
+//! [`TriggerTiming::Tap`]: crate::TriggerTiming::Tap
+
+use std::time::{Duration, Instant};
+
+use crate::VKey;
+
+/// Outcome of releasing the trigger key of a pending press.
+#[derive(Debug)]
+pub(crate) struct ReleaseOutcome<T> {
+    /// actions of the tap hotkeys to fire
+    pub fire: Vec<T>,
+    /// the key press was blocked and nothing fired, so it has to be sent again
+    pub replay: bool,
+}
+
+/// A press waiting for the release of the trigger key or the hold timers.
+#[derive(Debug)]
+pub(crate) struct PendingPress<T> {
+    /// the trigger key of the pending hotkeys
+    pub key: VKey,
+    /// keys whose release cancels the press (trigger and modifiers)
+    pub combo: Vec<VKey>,
+    started: Instant,
+    /// whether the key down was blocked
+    pub blocked: bool,
+    /// whether some hotkey already fired for this press
+    resolved: bool,
+    holds: Vec<(Duration, T)>,
+    taps: Vec<(Duration, T)>,
+}
+
+impl<T> PendingPress<T> {
+    pub fn new(key: VKey, combo: Vec<VKey>, started: Instant, blocked: bool) -> Self {
+        Self {
+            key,
+            combo,
+            started,
+            blocked,
+            resolved: false,
+            holds: Vec::new(),
+            taps: Vec::new(),
+        }
+    }
+
+    /// Adds an action that fires once the press lasted `duration`.
+    pub fn on_hold(&mut self, duration: Duration, action: T) {
+        self.holds.push((duration, action));
+    }
+
+    /// Adds an action that fires if the key is released within `max`.
+    pub fn on_tap(&mut self, max: Duration, action: T) {
+        self.taps.push((max, action));
+    }
+
+    /// Deadlines at which [`PendingPress::hold_elapsed`] must be called.
+    pub fn hold_deadlines(&self) -> Vec<Instant> {
+        self.holds
+            .iter()
+            .map(|(duration, _)| self.started + *duration)
+            .collect()
+    }
+
+    /// Returns the hold actions whose duration has been reached, each one is only returned once.
+    pub fn hold_elapsed(&mut self, now: Instant) -> Vec<T> {
+        let held = now.saturating_duration_since(self.started);
+        let (due, pending) = std::mem::take(&mut self.holds)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(duration, _)| *duration <= held);
+        self.holds = pending;
+        if !due.is_empty() {
+            self.resolved = true;
+        }
+        due.into_iter().map(|(_, action)| action).collect()
+    }
+
+    /// Consumes the press on the release of the trigger key.
+    pub fn release(mut self, now: Instant) -> ReleaseOutcome<T> {
+        // a hold that reached its deadline but was not yet processed still wins over taps
+        let mut fire = self.hold_elapsed(now);
+        if !self.resolved {
+            let held = now.saturating_duration_since(self.started);
+            fire = self
+                .taps
+                .into_iter()
+                .filter(|(max, _)| held <= *max)
+                .map(|(_, action)| action)
+                .collect();
+            self.resolved = !fire.is_empty();
+        }
+        ReleaseOutcome {
+            fire,
+            replay: self.blocked && !self.resolved,
+        }
+    }
+
+    /// Consumes the press when it is interrupted by another key or by the release
+    /// of a modifier, returns whether the blocked key down has to be sent again.
+    pub fn interrupt(self) -> bool {
+        self.blocked && !self.resolved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    const LIMIT: Duration = Duration::from_millis(300);
+
+    fn caps_lock_press(clock: &ManualClock) -> PendingPress<&'static str> {
+        let mut press = PendingPress::new(VKey::Capital, vec![VKey::Capital], clock.now(), true);
+        press.on_tap(LIMIT, "tap");
+        press.on_hold(LIMIT, "hold");
+        press
+    }
+
+    #[test]
+    fn test_quick_release_is_a_tap() {
+        let clock = ManualClock::new();
+        let mut press = caps_lock_press(&clock);
+
+        clock.advance(Duration::from_millis(100));
+        assert!(press.hold_elapsed(clock.now()).is_empty());
+
+        let outcome = press.release(clock.now());
+        assert_eq!(outcome.fire, vec!["tap"]);
+        assert!(!outcome.replay, "The tap consumed the blocked key");
+    }
+
+    #[test]
+    fn test_long_press_is_a_hold() {
+        let clock = ManualClock::new();
+        let mut press = caps_lock_press(&clock);
+
+        clock.advance(LIMIT);
+        assert_eq!(press.hold_elapsed(clock.now()), vec!["hold"]);
+        assert!(
+            press.hold_elapsed(clock.now()).is_empty(),
+            "Hold actions should only fire once"
+        );
+
+        clock.advance(Duration::from_millis(500));
+        let outcome = press.release(clock.now());
+        assert!(outcome.fire.is_empty(), "Tap should not fire after a hold");
+        assert!(!outcome.replay);
+    }
+
+    #[test]
+    fn test_release_after_unprocessed_hold_deadline() {
+        let clock = ManualClock::new();
+        let press = caps_lock_press(&clock);
+
+        clock.advance(LIMIT + Duration::from_millis(1));
+        let outcome = press.release(clock.now());
+        assert_eq!(outcome.fire, vec!["hold"]);
+    }
+
+    #[test]
+    fn test_escalating_holds() {
+        let clock = ManualClock::new();
+        let mut press = PendingPress::new(VKey::A, vec![VKey::A], clock.now(), false);
+        press.on_hold(Duration::from_millis(300), "short");
+        press.on_hold(Duration::from_millis(1000), "long");
+
+        clock.advance(Duration::from_millis(300));
+        assert_eq!(press.hold_elapsed(clock.now()), vec!["short"]);
+        clock.advance(Duration::from_millis(700));
+        assert_eq!(press.hold_elapsed(clock.now()), vec!["long"]);
+    }
+
+    #[test]
+    fn test_blocked_press_without_outcome_is_replayed() {
+        let clock = ManualClock::new();
+        let mut press = PendingPress::new(VKey::Capital, vec![VKey::Capital], clock.now(), true);
+        press.on_hold(LIMIT, "hold");
+
+        clock.advance(Duration::from_millis(100));
+        let outcome = press.release(clock.now());
+        assert!(outcome.fire.is_empty());
+        assert!(
+            outcome.replay,
+            "Released before the hold, key must be sent again"
+        );
+
+        let mut press = PendingPress::new(VKey::Capital, vec![VKey::Capital], clock.now(), true);
+        press.on_tap(LIMIT, "tap");
+        clock.advance(Duration::from_millis(400));
+        let outcome = press.release(clock.now());
+        assert!(outcome.fire.is_empty());
+        assert!(
+            outcome.replay,
+            "Held too long for a tap, key must be sent again"
+        );
+    }
+
+    #[test]
+    fn test_interrupted_press() {
+        let clock = ManualClock::new();
+        let press = caps_lock_press(&clock);
+        assert!(
+            press.interrupt(),
+            "Unresolved blocked press must be replayed"
+        );
+
+        let mut press = caps_lock_press(&clock);
+        clock.advance(LIMIT);
+        press.hold_elapsed(clock.now());
+        assert!(!press.interrupt(), "Resolved press must not be replayed");
+
+        let press =
+            PendingPress::<&str>::new(VKey::Capital, vec![VKey::Capital], clock.now(), false);
+        assert!(!press.interrupt(), "Passed key must not be replayed");
+    }
+}
@@ -5,36 +5,51 @@
 
 use crate::error::{Result, WHKError};
 use crate::events::{EventLoopEvent, KeyAction, KeyboardInputEvent};
+use crate::output::{InputEvent, InputSink, SendInputSink};
+use crate::remap::REMAPS;
 use crate::state::KEYBOARD_STATE;
 use crate::{log_on_dev, VKey};
+use arc_swap::ArcSwapOption;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::thread;
 use std::time::Duration;
-use windows::Win32::Foundation::{HANDLE, LPARAM, LRESULT, WPARAM};
+use windows::Win32::Foundation::{HANDLE, HWND, LPARAM, LRESULT, WPARAM};
 use windows::Win32::System::Power::{
     RegisterSuspendResumeNotification, DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS,
 };
 use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK};
 use windows::Win32::UI::Input::KeyboardAndMouse::{
     SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
     VIRTUAL_KEY,
 };
 use windows::Win32::UI::WindowsAndMessaging::{
     CallNextHookEx, DispatchMessageW, GetMessageW, PostThreadMessageW, SetWindowsHookExW,
-    TranslateMessage, DEVICE_NOTIFY_CALLBACK, KBDLLHOOKSTRUCT, MSG, PBT_APMRESUMEAUTOMATIC,
-    PBT_APMRESUMESUSPEND, WH_KEYBOARD_LL, WM_KEYDOWN, WM_KEYUP, WM_QUIT, WM_SYSKEYDOWN,
-    WM_SYSKEYUP,
+    TranslateMessage, UnhookWindowsHookEx, DEVICE_NOTIFY_CALLBACK, EVENT_SYSTEM_FOREGROUND,
+    KBDLLHOOKSTRUCT, MSG, PBT_APMRESUMEAUTOMATIC, PBT_APMRESUMESUSPEND, WH_KEYBOARD_LL,
+    WH_MOUSE_LL, WINEVENT_OUTOFCONTEXT, WM_KEYDOWN, WM_KEYUP, WM_LBUTTONDOWN, WM_MBUTTONDOWN,
+    WM_QUIT, WM_RBUTTONDOWN, WM_SYSKEYDOWN, WM_SYSKEYUP, WM_XBUTTONDOWN,
 };
 
 /// Timeout for blocking key events, measured in milliseconds.
-const TIMEOUT: Duration = Duration::from_millis(250);
+pub(crate) const TIMEOUT: Duration = Duration::from_millis(250);
 
 /// Unassigned Virtual Key code used to suppress Windows Key events.
 const SILENT_KEY: VIRTUAL_KEY = VIRTUAL_KEY(0xE8);
 
+/// Value of `dwExtraInfo` on the key events injected by this crate,
+/// the hook lets them pass without processing them again.
+pub(crate) const INJECTED_TAG: usize = 0x57484B; // "WHK"
+
 static STARTED: AtomicBool = AtomicBool::new(false);
 static HOOK_THREAD_ID: AtomicU32 = AtomicU32::new(0);
 
+/// Whether the event loop waits for mouse clicks, the mouse hook ignores them otherwise.
+static MOUSE_CLICKS_WANTED: AtomicBool = AtomicBool::new(false);
+
+/// Destination of the key events sent by the event loop, `SendInput` if unset.
+static KEY_SINK: ArcSwapOption<Box<dyn InputSink>> = ArcSwapOption::const_empty();
+
 /// Starts the keyboard hook thread.
 pub fn start() -> Result<()> {
     if STARTED.load(Ordering::Relaxed) {
@@ -43,16 +58,24 @@ pub fn start() -> Result<()> {
 
     // Create/clear keyboard state
     KEYBOARD_STATE.lock().unwrap().clear();
+    REMAPS.lock().unwrap().release_all();
 
     let (tx, rx) = crossbeam_channel::unbounded::<bool>();
     thread::spawn(move || unsafe {
-        let Ok(_keyborad_handle) =
+        let Ok(keyboard_handle) =
             SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_hook_proc), None, 0)
         else {
             tx.send(false).unwrap();
             return;
         };
 
+        let Ok(mouse_handle) = SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_hook_proc), None, 0)
+        else {
+            let _ = UnhookWindowsHookEx(keyboard_handle);
+            tx.send(false).unwrap();
+            return;
+        };
+
         let mut recipient = DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS {
             Callback: Some(power_sleep_resume_proc),
             ..Default::default()
@@ -65,6 +88,20 @@ pub fn start() -> Result<()> {
             return;
         };
 
+        // hotstrings still work without it, their buffer is just not reset on window changes
+        let foreground_handle = SetWinEventHook(
+            EVENT_SYSTEM_FOREGROUND,
+            EVENT_SYSTEM_FOREGROUND,
+            None,
+            Some(foreground_event_proc),
+            0,
+            0,
+            WINEVENT_OUTOFCONTEXT,
+        );
+        if foreground_handle.is_invalid() {
+            log_on_dev!("Failed to watch the foreground window");
+        }
+
         tx.send(true).unwrap();
         HOOK_THREAD_ID.store(GetCurrentThreadId(), Ordering::Relaxed);
 
@@ -73,6 +110,12 @@ pub fn start() -> Result<()> {
             let _ = TranslateMessage(&msg);
             DispatchMessageW(&msg);
         }
+
+        if !foreground_handle.is_invalid() {
+            let _ = UnhookWinEvent(foreground_handle);
+        }
+        let _ = UnhookWindowsHookEx(mouse_handle);
+        let _ = UnhookWindowsHookEx(keyboard_handle);
     });
 
     if rx.recv()? {
@@ -124,12 +167,15 @@ unsafe extern "system" fn keyboard_hook_proc(code: i32, wparam: WPARAM, lparam:
     };
 
     let vk_code = event_data.vkCode as u16;
-    if vk_code == SILENT_KEY.0 {
+    if vk_code == SILENT_KEY.0 || event_data.dwExtraInfo == INJECTED_TAG {
         return next();
     }
 
     match event_type {
         WM_KEYDOWN | WM_SYSKEYDOWN => {
+            let source = VKey::from_vk_code(vk_code);
+            let remapped = REMAPS.lock().unwrap().key_down(source);
+            let vk_code = remapped.map_or(vk_code, |key| key.to_vk_code());
             let state = {
                 let mut state = KEYBOARD_STATE.lock().unwrap();
                 state.keydown(vk_code);
@@ -151,7 +197,7 @@ unsafe extern "system" fn keyboard_hook_proc(code: i32, wparam: WPARAM, lparam:
             // Wait for response on how to handle event
             if let Ok(action) = response_rx.recv_timeout(TIMEOUT) {
                 match action {
-                    KeyAction::Block => {
+                    KeyAction::Block | KeyAction::Suppress => {
                         if is_win_pressed {
                             // to avoid windows alone key opening the start menu,
                             // we need to send a silent key.
@@ -162,8 +208,16 @@ unsafe extern "system" fn keyboard_hook_proc(code: i32, wparam: WPARAM, lparam:
                     KeyAction::Allow => {}
                 }
             }
+            // the source key is replaced by the target key
+            if let Some(key) = remapped {
+                REMAPS.lock().unwrap().injected(source);
+                send_key_events(&[(key, true)]);
+                return LRESULT(1);
+            }
         }
         WM_KEYUP | WM_SYSKEYUP => {
+            let remapped = REMAPS.lock().unwrap().key_up(vk_code.into());
+            let vk_code = remapped.map_or(vk_code, |(key, _)| key.to_vk_code());
             let state = {
                 let mut state = KEYBOARD_STATE.lock().unwrap();
                 state.keyup(vk_code);
@@ -181,13 +235,30 @@ unsafe extern "system" fn keyboard_hook_proc(code: i32, wparam: WPARAM, lparam:
             })
             .send();
 
-            // we can't block key up events as this can cause issues on applications with inifinite key down states
+            // we only block key up events on explicit request, as this can cause issues on
+            // applications with inifinite key down states
             if let Ok(action) = response_rx.recv_timeout(TIMEOUT) {
-                if action == KeyAction::Block && VKey::from_vk_code(vk_code).is_windows_key() {
-                    // sending silent key will cause the windows keyup event to be ignored
+                let key = VKey::from_vk_code(vk_code);
+                if action == KeyAction::Suppress {
+                    // an injected remap target is still released, or it would stay held
+                    if let Some((key, true)) = remapped {
+                        send_key_events(&[(key, false)]);
+                    }
+                    return LRESULT(1);
+                }
+                if action == KeyAction::Block && (key.is_windows_key() || key.is_menu_key()) {
+                    // sending silent key will cause the windows keyup event to be ignored,
+                    // and the menu bar to not be activated on alt keyup.
                     send_silent_key();
                 }
             }
+            // the release of a remapped key is only sent to pair an injected press
+            if let Some((key, injected)) = remapped {
+                if injected {
+                    send_key_events(&[(key, false)]);
+                }
+                return LRESULT(1);
+            }
         }
         _ => {}
     };
@@ -195,6 +266,62 @@ unsafe extern "system" fn keyboard_hook_proc(code: i32, wparam: WPARAM, lparam:
     next()
 }
 
+/// Sets whether the mouse clicks are reported to the event loop.
+pub(crate) fn report_mouse_clicks(wanted: bool) {
+    MOUSE_CLICKS_WANTED.store(wanted, Ordering::Relaxed);
+}
+
+/// Hook procedure for mouse events, only button presses are reported to the event
+/// loop, and only when it waits for them, see [`report_mouse_clicks`].
+/// https://learn.microsoft.com/en-us/windows/win32/winmsg/lowlevelmouseproc
+unsafe extern "system" fn mouse_hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code >= 0
+        && MOUSE_CLICKS_WANTED.load(Ordering::Relaxed)
+        && matches!(
+            wparam.0 as u32,
+            WM_LBUTTONDOWN | WM_RBUTTONDOWN | WM_MBUTTONDOWN | WM_XBUTTONDOWN
+        )
+    {
+        EventLoopEvent::MouseButtonDown.send();
+    }
+    CallNextHookEx(None, code, wparam, lparam)
+}
+
+/// Reports the foreground window changes to the event loop.
+/// https://learn.microsoft.com/en-us/windows/win32/api/winuser/nc-winuser-wineventproc
+unsafe extern "system" fn foreground_event_proc(
+    _hook: HWINEVENTHOOK,
+    _event: u32,
+    _hwnd: HWND,
+    _id_object: i32,
+    _id_child: i32,
+    _event_thread: u32,
+    _event_time: u32,
+) {
+    EventLoopEvent::ForegroundChanged.send();
+}
+
+/// Injects key events tagged with [`INJECTED_TAG`], `true` stands for a key down.
+pub(crate) fn send_key_events(events: &[(VKey, bool)]) {
+    let events: Vec<InputEvent> = events
+        .iter()
+        .map(|&(key, down)| InputEvent::Key { key, down })
+        .collect();
+    let sent = match KEY_SINK.load().as_ref() {
+        Some(sink) => sink.send(&events),
+        None => SendInputSink.send(&events),
+    };
+    if sent.is_err() {
+        log_on_dev!("Failed to send key events");
+    }
+}
+
+/// Replaces the destination of [`send_key_events`], to check the injected sequence.
+#[cfg(test)]
+pub(crate) fn set_key_sink<S: InputSink + 'static>(sink: S) {
+    KEY_SINK.store(Some(std::sync::Arc::new(Box::new(sink))));
+}
+
 /// Sends a keydown and keyup event for Unassigned Virtual Key 0xE8.
 unsafe fn send_silent_key() {
     let inputs = [
@@ -2,14 +2,64 @@
 //! A hotkey is composed of a trigger key, one or more modifier keys, and a callback function
 //! that is executed when the hotkey is triggered.
 
+use crate::client_executor::HotkeyCall;
+use crate::clock;
+use crate::decision::{DecisionCallback, DEFAULT_DECISION_BUDGET};
+use crate::events::{HotkeyEvent, KeyAction};
+use crate::group::GroupId;
+use crate::layer::{LayerAction, LayerSwitch};
 use crate::state::KeyboardState;
+use crate::usage::Usage;
 use crate::VKey;
+use std::cmp::Ordering;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::sync::Arc;
+use std::time::Duration;
+
+pub(crate) type HotkeyCallback = Arc<Box<dyn Fn(&HotkeyEvent) + Send + Sync + 'static>>;
+pub(crate) type ExpireCallback = Arc<dyn Fn() + Send + Sync + 'static>;
+
+/// Identifies a registered hotkey.
+///
+/// Ids are allocated on registration unless one is supplied with [`Hotkey::id`],
+/// and stay the same when the hotkey is rebound to other keys.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HotkeyId(pub(crate) u64);
+
+impl HotkeyId {
+    /// Returns the numeric value of the id.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for HotkeyId {
+    fn from(value: u64) -> Self {
+        HotkeyId(value)
+    }
+}
+
+impl fmt::Display for HotkeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Optional descriptive information attached to a hotkey, e.g. for a settings UI.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HotkeyMetadata {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+}
 
 /// Defines what should happen with the key event after hotkey triggers
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TriggerBehavior {
     /// Allow the key event to propagate to other applications
     PassThrough,
@@ -19,41 +69,149 @@ pub enum TriggerBehavior {
 
 /// Defines when a hotkey should trigger
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TriggerTiming {
     /// Trigger when the key combination is pressed down
     OnKeyDown,
     /// Trigger when the trigger key is released
     OnKeyUp,
+    /// Trigger once the key combination has been held for the given duration
+    OnHold(Duration),
+    /// Trigger when the trigger key is released within `max` after being pressed,
+    /// without any other key pressed in between
+    Tap { max: Duration },
+    /// Trigger when a modifier key is released after being pressed alone, without any
+    /// other key or mouse button pressed in between and, if set, held at most `max`
+    ModifierTap { max: Option<Duration> },
+}
+
+/// Defines how the pressed modifiers are compared to the modifiers of a hotkey.
+///
+/// When several hotkeys of the same priority match the same event, the strictest mode
+/// wins: an `Exact` hotkey is preferred over an `AtLeast` one, which is preferred over
+/// an `AnyOf` one.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ModifierMatch {
+    /// The pressed modifiers must be exactly the hotkey modifiers,
+    /// "Win + A" won't trigger if "Win + Alt + A" is pressed
+    #[default]
+    Exact,
+    /// All the hotkey modifiers must be pressed, other modifiers are allowed,
+    /// "Ctrl + A" will trigger if "Ctrl + Shift + A" is pressed
+    AtLeast,
+    /// At least one of the hotkey modifiers must be pressed, modifiers that are not
+    /// part of the hotkey are not allowed, "[Ctrl, Alt] + A" will trigger on "Ctrl + A",
+    /// "Alt + A" or "Ctrl + Alt + A" but not on "Ctrl + Shift + A"
+    AnyOf,
+}
+
+/// Rate at which a hotkey keeps firing while its combination is held,
+/// see [`Hotkey::repeat_while_held`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RepeatRate {
+    /// time between the press and the first repeat
+    pub initial_delay: Duration,
+    /// time between two repeats
+    pub interval: Duration,
 }
 
 /// Represents a keyboard shortcut that triggers an action
 pub struct Hotkey {
+    /// id of the hotkey, allocated on registration if not set
+    pub id: Option<HotkeyId>,
+    /// descriptive information about the hotkey
+    pub metadata: HotkeyMetadata,
     /// key that must be pressed to trigger this hotkey
     pub trigger_key: VKey,
-    /// when the hotkey should trigger (on key down or key up)
+    /// when the hotkey should trigger (on key down, key up, hold or tap)
     pub trigger_timing: TriggerTiming,
     /// keys that must be pressed before the trigger key ex: [CTRL] + [A]
     pub modifiers: Vec<VKey>,
+    /// how the pressed modifiers are compared to `modifiers`
+    pub modifier_match: ModifierMatch,
     /// action to perform when this hotkey is triggered
     pub behaviour: TriggerBehavior,
     /// will ignore the `paused` global state
     pub bypass_pause: bool,
+    /// disabled hotkeys stay registered but never trigger
+    pub enabled: bool,
+    /// layer the hotkey belongs to, `None` for the base layer which is always active
+    pub layer: Option<String>,
+    /// change of the active layers when the hotkey triggers
+    pub layer_action: Option<LayerAction>,
+    /// group the hotkey was registered in, see [`crate::HotkeyGroup`]
+    pub group: Option<GroupId>,
+    /// hotkeys with a higher priority win when several hotkeys match the same event
+    pub priority: i32,
     /// if true, the hotkey will only trigger if keys was pressed in a strict sequence
     pub strict_sequence: bool,
+    /// number of consecutive taps of the combination needed to trigger this hotkey
+    pub taps: u8,
+    /// maximum time allowed between two consecutive taps
+    pub tap_window: Duration,
+    /// rate at which the hotkey keeps firing while held, instead of the OS auto-repeat
+    pub repeat_rate: Option<RepeatRate>,
+    /// number of times the callback can run before the hotkey is unregistered
+    pub max_uses: Option<u32>,
+    /// time after registration at which the hotkey is unregistered
+    pub expires_after: Option<Duration>,
+    /// callback function to execute when the hotkey expires without being used
+    pub expire_callback: Option<ExpireCallback>,
+    /// uses left of the registered hotkey
+    pub(crate) usage: Arc<Usage>,
+    /// rank of the registration among all the registered hotkeys, breaks precedence ties
+    pub(crate) registration: u64,
+    /// minimum time between two runs of the callback
+    pub cooldown: Duration,
+    /// what happens with the key when the hotkey matches during its cooldown,
+    /// `None` to keep `behaviour`
+    pub cooldown_behavior: Option<TriggerBehavior>,
+    /// decides at runtime whether the hotkey handles the key event
+    pub decision: Option<DecisionCallback>,
+    /// time the decision callback has to answer
+    pub decision_budget: Duration,
+    /// action taken if the decision callback doesn't answer within its budget
+    pub decision_fallback: KeyAction,
     /// callback function to execute when this hotkey is triggered
-    pub callback: Arc<Box<dyn Fn() + Send + Sync + 'static>>,
+    pub callback: HotkeyCallback,
+    /// callback function to execute when the combination is released after triggering
+    pub release_callback: Option<HotkeyCallback>,
 }
 
 impl Hotkey {
     fn base() -> Hotkey {
         Hotkey {
+            id: None,
+            metadata: HotkeyMetadata::default(),
             trigger_key: VKey::None,
             modifiers: Vec::new(),
+            modifier_match: ModifierMatch::Exact,
             behaviour: TriggerBehavior::StopPropagation,
             trigger_timing: TriggerTiming::OnKeyDown,
             bypass_pause: false,
+            enabled: true,
+            layer: None,
+            layer_action: None,
+            group: None,
+            priority: 0,
             strict_sequence: false,
-            callback: Arc::new(Box::new(|| {})),
+            taps: 1,
+            tap_window: Duration::ZERO,
+            repeat_rate: None,
+            max_uses: None,
+            expires_after: None,
+            expire_callback: None,
+            usage: Arc::default(),
+            registration: 0,
+            cooldown: Duration::ZERO,
+            cooldown_behavior: None,
+            decision: None,
+            decision_budget: DEFAULT_DECISION_BUDGET,
+            decision_fallback: KeyAction::Allow,
+            callback: Arc::new(Box::new(|_| {})),
+            release_callback: None,
         }
     }
 
@@ -64,16 +222,63 @@ impl Hotkey {
         F: Fn() + Send + Sync + 'static,
     {
         Self {
+            id: None,
+            metadata: HotkeyMetadata::default(),
             trigger_key,
             behaviour: TriggerBehavior::StopPropagation,
             trigger_timing: TriggerTiming::OnKeyDown,
             bypass_pause: false,
+            enabled: true,
+            layer: None,
+            layer_action: None,
+            group: None,
+            priority: 0,
             strict_sequence: false,
+            taps: 1,
+            tap_window: Duration::ZERO,
+            repeat_rate: None,
+            max_uses: None,
+            expires_after: None,
+            expire_callback: None,
+            usage: Arc::default(),
+            registration: 0,
+            cooldown: Duration::ZERO,
+            cooldown_behavior: None,
             modifiers: modifiers.as_ref().to_vec(),
-            callback: Arc::new(Box::new(callback)),
+            modifier_match: ModifierMatch::Exact,
+            decision: None,
+            decision_budget: DEFAULT_DECISION_BUDGET,
+            decision_fallback: KeyAction::Allow,
+            callback: Arc::new(Box::new(move |_| callback())),
+            release_callback: None,
         }
     }
 
+    /// Creates a new `Hotkey` whose callback receives the [`HotkeyEvent`] that
+    /// triggered it, so a handler shared across several hotkeys can tell which one fired.
+    pub fn with_context<M, F>(trigger_key: VKey, modifiers: M, callback: F) -> Hotkey
+    where
+        M: AsRef<[VKey]>,
+        F: Fn(&HotkeyEvent) + Send + Sync + 'static,
+    {
+        Hotkey::base()
+            .trigger(trigger_key)
+            .modifiers(modifiers)
+            .action_with_context(callback)
+    }
+
+    /// Creates a hotkey triggered by pressing and releasing a modifier key alone,
+    /// e.g. tapping the `WIN` key.
+    ///
+    /// The modifier press itself is never blocked, with [`TriggerBehavior::StopPropagation`]
+    /// the release is suppressed so the Start menu or the window menu bar are not opened.
+    pub fn modifier_tap<F>(modifier: VKey, callback: F) -> Hotkey
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        Hotkey::new(modifier, [], callback).trigger_timing(TriggerTiming::ModifierTap { max: None })
+    }
+
     /// last key is used as trigger
     pub fn from_keys<T: AsRef<[VKey]>>(keys: T) -> Self {
         let mut keys: Vec<VKey> = keys.as_ref().to_vec();
@@ -84,6 +289,36 @@ impl Hotkey {
         hotkey.modifiers(keys)
     }
 
+    /// Sets the id of the hotkey, registration fails if the id is already in use.
+    pub fn id<I: Into<HotkeyId>>(mut self, id: I) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Sets a human readable name for the hotkey
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.metadata.name = Some(name.into());
+        self
+    }
+
+    /// Sets a description of what the hotkey does
+    pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+        self.metadata.description = Some(description.into());
+        self
+    }
+
+    /// Sets the category of the hotkey, e.g. "Navigation"
+    pub fn category<S: Into<String>>(mut self, category: S) -> Self {
+        self.metadata.category = Some(category.into());
+        self
+    }
+
+    /// Adds a tag to the hotkey
+    pub fn tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.metadata.tags.push(tag.into());
+        self
+    }
+
     pub fn trigger(mut self, key: VKey) -> Self {
         self.trigger_key = key;
         self
@@ -94,45 +329,256 @@ impl Hotkey {
         self
     }
 
+    /// Sets how the pressed modifiers are compared to the hotkey modifiers
+    pub fn modifier_match(mut self, mode: ModifierMatch) -> Self {
+        self.modifier_match = mode;
+        self
+    }
+
     /// Sets the behavior when hotkey triggers
     pub fn behavior(mut self, action: TriggerBehavior) -> Self {
         self.behaviour = action;
         self
     }
 
+    /// Registers the hotkey disabled, see [`crate::HotkeyManager::set_enabled`].
+    pub fn disabled(mut self) -> Self {
+        self.enabled = false;
+        self
+    }
+
+    /// Assigns the hotkey to a layer, it only triggers while the layer is active,
+    /// see [`crate::HotkeyManager::push_layer`].
+    pub fn layer<S: Into<String>>(mut self, layer: S) -> Self {
+        self.layer = Some(layer.into());
+        self
+    }
+
+    /// Changes the active layers when the hotkey triggers, before its callback runs.
+    ///
+    /// A [`LayerAction::Momentary`] layer stays active while the whole combination
+    /// of the hotkey is held.
+    pub fn switch_layer(mut self, action: LayerAction) -> Self {
+        self.layer_action = Some(action);
+        self
+    }
+
     /// Makes the hotkey work even when global hotkeys are paused
     pub fn bypass_pause(mut self) -> Self {
         self.bypass_pause = true;
         self
     }
 
+    /// Sets the priority of the hotkey (0 by default), a higher priority wins when
+    /// several hotkeys match the same event, see [`crate::MatchPolicy`].
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
     pub fn strict_sequence(mut self) -> Self {
         self.strict_sequence = true;
         self
     }
 
-    /// Sets when the hotkey should trigger (on key down or key up)
+    /// Sets when the hotkey should trigger (on key down, key up, hold or tap)
     pub fn trigger_timing(mut self, timing: TriggerTiming) -> Self {
         self.trigger_timing = timing;
         self
     }
 
+    /// Makes the hotkey trigger only after the combination is tapped `count` times,
+    /// with at most `window` between two consecutive taps.
+    ///
+    /// A hotkey with fewer taps on the same combination is deferred until the
+    /// window elapses, so a single tap binding doesn't fire on the first tap of
+    /// a double tap.
+    pub fn taps(mut self, count: u8, window: Duration) -> Self {
+        self.taps = count.max(1);
+        self.tap_window = window;
+        self
+    }
+
+    /// Makes the hotkey fire when pressed, then again after `initial_delay` and every
+    /// `interval` while the combination is held, e.g. for volume controls.
+    ///
+    /// The OS auto-repeat of the trigger key doesn't run the callback. Releasing any key
+    /// of the combination stops the repeats, as do pausing the hotkeys (unless the hotkey
+    /// bypasses the pause) and stealing the keyboard. The repeat number is available in
+    /// [`HotkeyEvent::repeat`]. Only applies to `OnKeyDown` hotkeys.
+    pub fn repeat_while_held(mut self, initial_delay: Duration, interval: Duration) -> Self {
+        self.repeat_rate = Some(RepeatRate {
+            initial_delay,
+            interval,
+        });
+        self
+    }
+
+    /// Makes the hotkey fire only once, it is unregistered afterwards.
+    pub fn once(self) -> Self {
+        self.times(1)
+    }
+
+    /// Makes the hotkey fire at most `count` times, it is unregistered afterwards.
+    ///
+    /// Uses are consumed when the callback runs, on the event loop thread, so the
+    /// hotkey can't fire more than `count` times even if triggered in quick succession.
+    pub fn times(mut self, count: u32) -> Self {
+        self.max_uses = Some(count.max(1));
+        self
+    }
+
+    /// Unregisters the hotkey once `duration` elapsed after its registration.
+    pub fn expires_after(mut self, duration: Duration) -> Self {
+        self.expires_after = Some(duration);
+        self
+    }
+
+    /// Sets a callback run when the hotkey expires without having fired,
+    /// see [`Hotkey::expires_after`].
+    pub fn on_expire<F>(mut self, callback: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.expire_callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Makes the callback run at most once per `cooldown`, e.g. to ignore chattering
+    /// switches or auto-repeat.
+    ///
+    /// Matching presses during the cooldown don't run the callback, but the key is
+    /// still handled as set with [`Hotkey::cooldown_behavior`].
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Sets whether the key is blocked or passed through when the hotkey matches during
+    /// its cooldown, by default the hotkey behavior applies.
+    pub fn cooldown_behavior(mut self, behavior: TriggerBehavior) -> Self {
+        self.cooldown_behavior = Some(behavior);
+        self
+    }
+
+    /// Sets a callback deciding, each time the hotkey matches, whether it handles
+    /// the key event.
+    ///
+    /// Returning [`KeyAction::Block`] runs the hotkey callback and blocks the key,
+    /// returning [`KeyAction::Allow`] lets the key through as if the hotkey was not
+    /// registered, e.g. handle "Ctrl + V" only when the clipboard holds special content.
+    ///
+    /// The decision is taken synchronously while the key event is held, so it must be
+    /// quick. It only applies to `OnKeyDown` and `OnKeyUp` hotkeys, see
+    /// [`Hotkey::decision_budget`] for slow decisions.
+    pub fn decide<F>(mut self, decision: F) -> Self
+    where
+        F: Fn(&HotkeyEvent) -> KeyAction + Send + Sync + 'static,
+    {
+        self.decision = Some(Arc::new(decision));
+        self
+    }
+
+    /// Sets how long the decision callback has to answer (50ms by default) and the
+    /// action taken if it doesn't, [`KeyAction::Allow`] by default.
+    ///
+    /// The budget is capped at 200ms, as the hook lets the key through once it
+    /// waited 250ms for the event loop.
+    pub fn decision_budget(mut self, budget: Duration, fallback: KeyAction) -> Self {
+        self.decision_budget = budget;
+        self.decision_fallback = fallback;
+        self
+    }
+
     pub fn action<F>(mut self, action: F) -> Self
     where
         F: Fn() + Send + Sync + 'static,
+    {
+        self.callback = Arc::new(Box::new(move |_| action()));
+        self
+    }
+
+    /// Sets a callback that receives the [`HotkeyEvent`] that triggered the hotkey.
+    pub fn action_with_context<F>(mut self, action: F) -> Self
+    where
+        F: Fn(&HotkeyEvent) + Send + Sync + 'static,
     {
         self.callback = Arc::new(Box::new(action));
         self
     }
 
-    /// Executes the callback associated with the hotkey, in a separate thread.
+    /// Sets a callback run once the combination is released after the hotkey fired,
+    /// e.g. for push-to-talk.
+    ///
+    /// Each press is paired with exactly one release, which happens as soon as any key
+    /// of the combination is released, whatever the order, or when the hotkeys are paused
+    /// (unless the hotkey bypasses the pause), the capture stops or the keyboard state is
+    /// resynchronized. The release context has the `OnKeyUp` timing. Only applies to
+    /// `OnKeyDown` hotkeys.
+    pub fn on_release<F>(mut self, callback: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.release_callback = Some(Arc::new(Box::new(move |_| callback())));
+        self
+    }
+
+    /// Sets a release callback that receives the [`HotkeyEvent`] of the release,
+    /// see [`Hotkey::on_release`].
+    pub fn on_release_with_context<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&HotkeyEvent) + Send + Sync + 'static,
+    {
+        self.release_callback = Some(Arc::new(Box::new(callback)));
+        self
+    }
+
+    /// Executes the callback associated with the hotkey on the current thread,
+    /// with a context built from the expected keyboard state of the hotkey.
     pub fn execute(&self) {
-        (self.callback)()
+        let event = self.event(&self.generate_expected_keyboard_state(), 0);
+        (self.callback)(&event)
+    }
+
+    /// Builds the context of a trigger of this hotkey.
+    pub(crate) fn event(&self, state: &KeyboardState, repeat: u32) -> HotkeyEvent {
+        HotkeyEvent {
+            id: self.id.unwrap_or_default(),
+            trigger_key: self.trigger_key,
+            timing: self.trigger_timing,
+            state: state.clone(),
+            timestamp: clock::now(),
+            repeat,
+        }
+    }
+
+    /// Prepares a call of the callback for a trigger of this hotkey.
+    pub(crate) fn call(&self, state: &KeyboardState, repeat: u32) -> HotkeyCall {
+        HotkeyCall {
+            callback: self.callback.clone(),
+            cooldown: self.cooldown,
+            usage: self.usage.clone(),
+            layer_switch: self.layer_action.clone().map(|action| LayerSwitch {
+                action,
+                combo: self
+                    .modifiers
+                    .iter()
+                    .copied()
+                    .chain([self.trigger_key])
+                    .collect(),
+            }),
+            event: self.event(state, repeat),
+        }
     }
 
     /// Checks if current keyboard state should trigger hotkey callback.
     /// This should only be called if the most recent keypress is the
     /// trigger key for the hotkey.
+    ///
+    /// Side specific modifiers mean what they say: a hotkey declared with
+    /// `VKey::LControl` won't trigger while only `RControl` is held, and in `Exact`
+    /// mode holding the other side too prevents it. The generic `Control`, `Shift`
+    /// and `Menu` keys match either side, as do both windows keys.
     pub fn is_trigger_state(&self, changed: &VKey, state: &KeyboardState) -> bool {
         // last changed key must be the trigger key
         if self.trigger_key != *changed {
@@ -160,12 +606,50 @@ impl Hotkey {
             }
         }
 
-        // Verify modifier key states match exactly
-        // example hotkey "Win + A" won't trigger if "Win + Alt + A" is pressed
-        expected_state.is_win_pressed() == state.is_win_pressed()
-            && expected_state.is_menu_pressed() == state.is_menu_pressed()
-            && expected_state.is_shift_pressed() == state.is_shift_pressed()
-            && expected_state.is_control_pressed() == state.is_control_pressed()
+        // Side specific modifiers (LControl, RMenu...) only match that side, while the
+        // generic ones (Control, Menu, Shift) and the windows keys match either side.
+        let required: Vec<&VKey> = self
+            .modifiers
+            .iter()
+            .filter(|key| key.is_modifier_key())
+            .collect();
+        let trigger_is_pressed = self.trigger_timing != TriggerTiming::OnKeyUp;
+        // every pressed modifier must be part of the hotkey
+        let only_expected_pressed = state
+            .pressing
+            .iter()
+            .filter(|pressed| pressed.is_modifier_key())
+            .all(|pressed| {
+                required.iter().any(|key| key.matches(pressed))
+                    || (trigger_is_pressed && self.trigger_key.matches(pressed))
+            });
+
+        match self.modifier_match {
+            // example hotkey "Win + A" won't trigger if "Win + Alt + A" is pressed
+            ModifierMatch::Exact => {
+                required.iter().all(|key| state.is_down(**key)) && only_expected_pressed
+            }
+            ModifierMatch::AtLeast => required.iter().all(|key| state.is_down(**key)),
+            ModifierMatch::AnyOf => {
+                (required.is_empty() || required.iter().any(|key| state.is_down(**key)))
+                    && only_expected_pressed
+            }
+        }
+    }
+
+    /// Orders two hotkeys matching the same event, the one ordered first wins.
+    ///
+    /// The higher priority comes first, then the most specific hotkey: the strictest
+    /// [`ModifierMatch`], then strict sequence hotkeys, then the one with the most
+    /// modifiers. Equal hotkeys are then ordered by registration.
+    pub(crate) fn cmp_precedence(&self, other: &Hotkey) -> Ordering {
+        other
+            .priority
+            .cmp(&self.priority)
+            .then(self.modifier_match.cmp(&other.modifier_match))
+            .then(other.strict_sequence.cmp(&self.strict_sequence))
+            .then(other.modifiers.len().cmp(&self.modifiers.len()))
+            .then(self.registration.cmp(&other.registration))
     }
 
     /// Generates a `KeyboardState` representing the hotkey.
@@ -195,10 +679,22 @@ impl Hotkey {
 impl fmt::Debug for Hotkey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Hotkey")
+            .field("id", &self.id)
+            .field("metadata", &self.metadata)
             .field("trigger_key", &self.trigger_key)
             .field("trigger_action", &self.behaviour)
             .field("trigger_timing", &self.trigger_timing)
             .field("modifiers", &self.modifiers)
+            .field("modifier_match", &self.modifier_match)
+            .field("priority", &self.priority)
+            .field("enabled", &self.enabled)
+            .field("layer", &self.layer)
+            .field("layer_action", &self.layer_action)
+            .field("group", &self.group)
+            .field("taps", &self.taps)
+            .field("max_uses", &self.max_uses)
+            .field("expires_after", &self.expires_after)
+            .field("decision", &self.decision.as_ref().map(|_| "<decision>"))
             .field("callback", &"<callback>")
             .finish()
     }
@@ -210,6 +706,10 @@ impl PartialEq for Hotkey {
         self.trigger_key == other.trigger_key
             && self.modifiers == other.modifiers
             && self.trigger_timing == other.trigger_timing
+            && self.strict_sequence == other.strict_sequence
+            && self.taps == other.taps
+            && self.modifier_match == other.modifier_match
+            && self.layer == other.layer
     }
 }
 
@@ -218,5 +718,9 @@ impl Hash for Hotkey {
         self.trigger_key.hash(state);
         self.modifiers.hash(state);
         self.trigger_timing.hash(state);
+        self.strict_sequence.hash(state);
+        self.taps.hash(state);
+        self.modifier_match.hash(state);
+        self.layer.hash(state);
     }
 }
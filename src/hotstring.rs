@@ -0,0 +1,621 @@
+//! Hotstrings: actions triggered by typing a word, e.g. `btw` followed by a space.
+//!
+//! The typed characters are tracked in a rolling buffer, translated from the key
+//! presses with the US keyboard layout. The buffer is reset by the keys that move
+//! the caret (arrows, `Home`, `Escape`...), by shortcuts using `CTRL`, `ALT` or
+//! `WIN`, by mouse clicks and when another window comes to the foreground, as the
+//! next characters may be typed elsewhere.
+//! The Caps Lock toggle state is not tracked.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::error::{Result, WHKError};
+use crate::output::{TextOutput, TextTemplate};
+use crate::state::KeyboardState;
+use crate::{log_on_dev, VKey};
+
+pub(crate) type HotstringCallback = Arc<dyn Fn(&HotstringEvent) + Send + Sync + 'static>;
+
+/// Maximum number of typed characters kept in the buffer.
+const BUFFER_SIZE: usize = 100;
+
+/// Characters ending a word, a hotstring fires when one is typed right after it
+/// unless it is [`Hotstring::immediate`].
+pub const END_CHARS: &str = "-()[]{}':;\"/\\,.?!\n \t";
+
+/// Keys typing a character on the US layout, without and with `SHIFT`.
+const US_LAYOUT: &[(VKey, char, char)] = &[
+    (VKey::A, 'a', 'A'),
+    (VKey::B, 'b', 'B'),
+    (VKey::C, 'c', 'C'),
+    (VKey::D, 'd', 'D'),
+    (VKey::E, 'e', 'E'),
+    (VKey::F, 'f', 'F'),
+    (VKey::G, 'g', 'G'),
+    (VKey::H, 'h', 'H'),
+    (VKey::I, 'i', 'I'),
+    (VKey::J, 'j', 'J'),
+    (VKey::K, 'k', 'K'),
+    (VKey::L, 'l', 'L'),
+    (VKey::M, 'm', 'M'),
+    (VKey::N, 'n', 'N'),
+    (VKey::O, 'o', 'O'),
+    (VKey::P, 'p', 'P'),
+    (VKey::Q, 'q', 'Q'),
+    (VKey::R, 'r', 'R'),
+    (VKey::S, 's', 'S'),
+    (VKey::T, 't', 'T'),
+    (VKey::U, 'u', 'U'),
+    (VKey::V, 'v', 'V'),
+    (VKey::W, 'w', 'W'),
+    (VKey::X, 'x', 'X'),
+    (VKey::Y, 'y', 'Y'),
+    (VKey::Z, 'z', 'Z'),
+    (VKey::Digit0, '0', ')'),
+    (VKey::Digit1, '1', '!'),
+    (VKey::Digit2, '2', '@'),
+    (VKey::Digit3, '3', '#'),
+    (VKey::Digit4, '4', '$'),
+    (VKey::Digit5, '5', '%'),
+    (VKey::Digit6, '6', '^'),
+    (VKey::Digit7, '7', '&'),
+    (VKey::Digit8, '8', '*'),
+    (VKey::Digit9, '9', '('),
+    (VKey::Oem1, ';', ':'),
+    (VKey::OemPlus, '=', '+'),
+    (VKey::OemComma, ',', '<'),
+    (VKey::OemMinus, '-', '_'),
+    (VKey::OemPeriod, '.', '>'),
+    (VKey::Oem2, '/', '?'),
+    (VKey::Oem3, '`', '~'),
+    (VKey::Oem4, '[', '{'),
+    (VKey::Oem5, '\\', '|'),
+    (VKey::Oem6, ']', '}'),
+    (VKey::Oem7, '\'', '"'),
+    (VKey::Space, ' ', ' '),
+    (VKey::Return, '\n', '\n'),
+    (VKey::Tab, '\t', '\t'),
+    (VKey::Numpad0, '0', '0'),
+    (VKey::Numpad1, '1', '1'),
+    (VKey::Numpad2, '2', '2'),
+    (VKey::Numpad3, '3', '3'),
+    (VKey::Numpad4, '4', '4'),
+    (VKey::Numpad5, '5', '5'),
+    (VKey::Numpad6, '6', '6'),
+    (VKey::Numpad7, '7', '7'),
+    (VKey::Numpad8, '8', '8'),
+    (VKey::Numpad9, '9', '9'),
+    (VKey::Multiply, '*', '*'),
+    (VKey::Add, '+', '+'),
+    (VKey::Subtract, '-', '-'),
+    (VKey::Decimal, '.', '.'),
+    (VKey::Divide, '/', '/'),
+];
+
+/// Returns the character typed by `key` on the US layout.
+fn typed_char(key: VKey, shift: bool) -> Option<char> {
+    US_LAYOUT
+        .iter()
+        .find(|(layout_key, ..)| *layout_key == key)
+        .map(|(_, lower, upper)| if shift { *upper } else { *lower })
+}
+
+/// Identifies a registered hotstring.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HotstringId(pub(crate) u64);
+
+impl HotstringId {
+    /// Returns the numeric value of the id.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for HotstringId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Context received by the hotstring callbacks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotstringEvent {
+    /// The id of the hotstring that fired, as returned on registration.
+    pub id: HotstringId,
+    /// The trigger of the hotstring.
+    pub trigger: String,
+    /// The trigger as it was typed, which differs in case for case insensitive hotstrings.
+    pub typed: String,
+    /// The end character typed after the trigger, `None` for immediate hotstrings.
+    pub end_char: Option<char>,
+    /// When the hotstring fired.
+    pub timestamp: Instant,
+}
+
+/// A word triggering a callback when typed.
+#[derive(Clone)]
+pub struct Hotstring {
+    pub(crate) id: HotstringId,
+    pub trigger: String,
+    /// the typed word must have the same case as the trigger
+    pub case_sensitive: bool,
+    /// fires as soon as the trigger is typed, without waiting for an end character
+    pub immediate: bool,
+    /// fires even when the trigger is typed at the end of another word
+    pub inside_words: bool,
+    /// will ignore the `paused` global state
+    pub bypass_pause: bool,
+    pub(crate) callback: HotstringCallback,
+}
+
+impl Hotstring {
+    /// Creates a case insensitive hotstring, firing when `trigger` is typed
+    /// followed by one of the [`END_CHARS`].
+    pub fn new<S, F>(trigger: S, callback: F) -> Hotstring
+    where
+        S: Into<String>,
+        F: Fn() + Send + Sync + 'static,
+    {
+        Hotstring::with_context(trigger, move |_| callback())
+    }
+
+    /// Creates a hotstring whose callback receives the [`HotstringEvent`] that triggered it.
+    pub fn with_context<S, F>(trigger: S, callback: F) -> Hotstring
+    where
+        S: Into<String>,
+        F: Fn(&HotstringEvent) + Send + Sync + 'static,
+    {
+        Hotstring {
+            id: HotstringId::default(),
+            trigger: trigger.into(),
+            case_sensitive: false,
+            immediate: false,
+            inside_words: false,
+            bypass_pause: false,
+            callback: Arc::new(callback),
+        }
+    }
+
+    /// Creates a hotstring replacing the typed trigger with `replacement`, typed
+    /// with the default [`TextOutput`].
+    pub fn expansion<S: Into<String>>(trigger: S, replacement: TextTemplate) -> Hotstring {
+        Hotstring::expansion_with(trigger, replacement, TextOutput::default())
+    }
+
+    /// Creates a hotstring replacing the typed trigger with `replacement`, typed with `output`.
+    ///
+    /// The trigger and its end character are erased, the end character is typed
+    /// again after the replacement.
+    pub fn expansion_with<S: Into<String>>(
+        trigger: S,
+        replacement: TextTemplate,
+        output: TextOutput,
+    ) -> Hotstring {
+        Hotstring::with_context(trigger, move |event| {
+            let mut replacement = replacement.clone();
+            let mut erase = event.typed.chars().count();
+            if let Some(end_char) = event.end_char {
+                replacement.push_text(&end_char.to_string());
+                erase += 1;
+            }
+            if output.replace(erase, &replacement).is_err() {
+                log_on_dev!("Failed to expand hotstring");
+            }
+        })
+    }
+
+    /// Only fires when typed with the same case as the trigger.
+    pub fn case_sensitive(mut self) -> Self {
+        self.case_sensitive = true;
+        self
+    }
+
+    /// Fires as soon as the last character of the trigger is typed.
+    pub fn immediate(mut self) -> Self {
+        self.immediate = true;
+        self
+    }
+
+    /// Also fires when the trigger ends another word, e.g. `btw` in `abtw`.
+    pub fn inside_words(mut self) -> Self {
+        self.inside_words = true;
+        self
+    }
+
+    /// Makes the hotstring work even when global hotkeys are paused
+    pub fn bypass_pause(mut self) -> Self {
+        self.bypass_pause = true;
+        self
+    }
+
+    /// Returns the end of `typed` matching the trigger, if any.
+    fn match_end<'a>(&self, typed: &'a str) -> Option<&'a str> {
+        let len = self.trigger.chars().count();
+        let start = typed.char_indices().rev().nth(len - 1)?.0;
+        let end = &typed[start..];
+        let same = if self.case_sensitive {
+            end == self.trigger
+        } else {
+            end.to_lowercase() == self.trigger.to_lowercase()
+        };
+        let at_word_start = typed[..start]
+            .chars()
+            .next_back()
+            .is_none_or(|previous| !previous.is_alphanumeric());
+        (same && (self.inside_words || at_word_start)).then_some(end)
+    }
+
+    /// Checks whether both hotstrings fire on the same typed text.
+    fn is_same_as(&self, other: &Hotstring) -> bool {
+        self.case_sensitive == other.case_sensitive
+            && self.immediate == other.immediate
+            && self.inside_words == other.inside_words
+            && if self.case_sensitive {
+                self.trigger == other.trigger
+            } else {
+                self.trigger.to_lowercase() == other.trigger.to_lowercase()
+            }
+    }
+}
+
+impl fmt::Debug for Hotstring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hotstring")
+            .field("id", &self.id)
+            .field("trigger", &self.trigger)
+            .field("case_sensitive", &self.case_sensitive)
+            .field("immediate", &self.immediate)
+            .field("inside_words", &self.inside_words)
+            .field("bypass_pause", &self.bypass_pause)
+            .finish()
+    }
+}
+
+/// The registered hotstrings and the characters typed so far.
+#[derive(Debug)]
+pub(crate) struct HotstringMatcher {
+    hotstrings: Vec<Hotstring>,
+    buffer: String,
+    next_id: u64,
+}
+
+impl HotstringMatcher {
+    pub const fn new() -> Self {
+        Self {
+            hotstrings: Vec::new(),
+            buffer: String::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn register(&mut self, mut hotstring: Hotstring) -> Result<HotstringId> {
+        if hotstring.trigger.is_empty() {
+            return Err(WHKError::InvalidHotstring(hotstring.trigger));
+        }
+        if self
+            .hotstrings
+            .iter()
+            .any(|registered| registered.is_same_as(&hotstring))
+        {
+            return Err(WHKError::HotstringAlreadyRegistered(hotstring.trigger));
+        }
+        self.next_id += 1;
+        hotstring.id = HotstringId(self.next_id);
+        self.hotstrings.push(hotstring);
+        Ok(HotstringId(self.next_id))
+    }
+
+    pub fn unregister(&mut self, id: HotstringId) -> Option<Hotstring> {
+        let position = self.hotstrings.iter().position(|hs| hs.id == id)?;
+        Some(self.hotstrings.remove(position))
+    }
+
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// Whether a hotstring may be being typed, a mouse click would reset the buffer.
+    pub fn is_typing(&self) -> bool {
+        !self.hotstrings.is_empty() && !self.buffer.is_empty()
+    }
+
+    /// Updates the buffer with a key press, returns the callback of the hotstring
+    /// it completes, if any, along with its event.
+    ///
+    /// The first matching hotstring in registration order fires, then the buffer is reset.
+    pub fn key_down(
+        &mut self,
+        key: VKey,
+        state: &KeyboardState,
+        paused: bool,
+        now: Instant,
+    ) -> Option<(HotstringCallback, HotstringEvent)> {
+        if key.is_modifier_key() || matches!(key, VKey::Capital | VKey::Numlock | VKey::Scroll) {
+            return None;
+        }
+        if state.is_control_pressed() || state.is_menu_pressed() || state.is_win_pressed() {
+            self.reset();
+            return None;
+        }
+        if key == VKey::Back {
+            self.buffer.pop();
+            return None;
+        }
+        let Some(c) = typed_char(key, state.is_shift_pressed()) else {
+            self.reset();
+            return None;
+        };
+
+        self.push(c);
+        // the other hotstrings are matched before the end character
+        let before = &self.buffer[..self.buffer.len() - c.len_utf8()];
+        let is_end_char = END_CHARS.contains(c);
+        let fired = self
+            .hotstrings
+            .iter()
+            .filter(|hs| !paused || hs.bypass_pause)
+            .find_map(|hs| {
+                let (typed, end_char) = if hs.immediate {
+                    (hs.match_end(&self.buffer)?, None)
+                } else if is_end_char {
+                    (hs.match_end(before)?, Some(c))
+                } else {
+                    return None;
+                };
+                let event = HotstringEvent {
+                    id: hs.id,
+                    trigger: hs.trigger.clone(),
+                    typed: typed.to_owned(),
+                    end_char,
+                    timestamp: now,
+                };
+                Some((hs.callback.clone(), event))
+            });
+
+        if fired.is_some() {
+            self.reset();
+        }
+        fired
+    }
+
+    fn push(&mut self, c: char) {
+        self.buffer.push(c);
+        if self.buffer.chars().count() > BUFFER_SIZE {
+            self.buffer.remove(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::RecordingSink;
+
+    /// Types `text` with the US layout, holding `SHIFT` for the upper characters.
+    fn type_text(matcher: &mut HotstringMatcher, text: &str) -> Vec<HotstringEvent> {
+        let mut fired = Vec::new();
+        for c in text.chars() {
+            let (key, lower, _) = US_LAYOUT
+                .iter()
+                .find(|(_, lower, upper)| *lower == c || *upper == c)
+                .expect("character on the US layout");
+            let mut state = KeyboardState::new();
+            if *lower != c {
+                state.keydown(VKey::LShift);
+            }
+            fired.extend(press(matcher, *key, &mut state));
+        }
+        fired
+    }
+
+    fn press(
+        matcher: &mut HotstringMatcher,
+        key: VKey,
+        state: &mut KeyboardState,
+    ) -> Option<HotstringEvent> {
+        state.keydown(key);
+        let fired = matcher.key_down(key, state, false, Instant::now());
+        state.keyup(key);
+        fired.map(|(_, event)| event)
+    }
+
+    fn triggers(events: &[HotstringEvent]) -> Vec<&str> {
+        events.iter().map(|event| event.trigger.as_str()).collect()
+    }
+
+    fn matcher(hotstrings: Vec<Hotstring>) -> HotstringMatcher {
+        let mut matcher = HotstringMatcher::new();
+        for hotstring in hotstrings {
+            matcher.register(hotstring).unwrap();
+        }
+        matcher
+    }
+
+    #[test]
+    fn test_end_char_required() {
+        let mut matcher = matcher(vec![Hotstring::new("btw", || {})]);
+
+        assert!(type_text(&mut matcher, "btw").is_empty());
+        let fired = type_text(&mut matcher, ".");
+        assert_eq!(triggers(&fired), vec!["btw"]);
+        assert_eq!(fired[0].end_char, Some('.'));
+
+        assert!(
+            type_text(&mut matcher, " btw").is_empty(),
+            "Buffer is reset after firing"
+        );
+        assert_eq!(triggers(&type_text(&mut matcher, "\n")), vec!["btw"]);
+    }
+
+    #[test]
+    fn test_immediate() {
+        let mut matcher = matcher(vec![Hotstring::new("@@", || {}).immediate()]);
+        let fired = type_text(&mut matcher, "mail @@");
+        assert_eq!(triggers(&fired), vec!["@@"]);
+        assert_eq!(fired[0].end_char, None);
+    }
+
+    #[test]
+    fn test_immediate_ending_with_end_char() {
+        let mut matcher = matcher(vec![
+            Hotstring::new("(c)", || {}).immediate(),
+            Hotstring::new(":)", || {}).immediate(),
+        ]);
+
+        let fired = type_text(&mut matcher, "copyright (c)");
+        assert_eq!(triggers(&fired), vec!["(c)"]);
+        assert_eq!(fired[0].typed, "(c)");
+        assert_eq!(fired[0].end_char, None);
+        assert_eq!(triggers(&type_text(&mut matcher, "hi :)")), vec![":)"]);
+    }
+
+    #[test]
+    fn test_case() {
+        let mut matcher = matcher(vec![
+            Hotstring::new("btw", || {}),
+            Hotstring::new("NASA", || {}).case_sensitive(),
+        ]);
+
+        let fired = type_text(&mut matcher, "BTW ");
+        assert_eq!(triggers(&fired), vec!["btw"]);
+        assert_eq!(fired[0].typed, "BTW");
+
+        assert!(type_text(&mut matcher, "nasa ").is_empty());
+        assert_eq!(triggers(&type_text(&mut matcher, "NASA ")), vec!["NASA"]);
+    }
+
+    #[test]
+    fn test_word_start() {
+        let mut matcher = matcher(vec![
+            Hotstring::new("btw", || {}),
+            Hotstring::new("ing", || {}).inside_words(),
+        ]);
+
+        assert!(type_text(&mut matcher, "abtw ").is_empty());
+        assert_eq!(triggers(&type_text(&mut matcher, "(btw)")), vec!["btw"]);
+        assert_eq!(triggers(&type_text(&mut matcher, "typing ")), vec!["ing"]);
+    }
+
+    #[test]
+    fn test_backspace() {
+        let mut matcher = matcher(vec![Hotstring::new("btw", || {})]);
+        let mut state = KeyboardState::new();
+
+        type_text(&mut matcher, "btx");
+        press(&mut matcher, VKey::Back, &mut state);
+        assert_eq!(triggers(&type_text(&mut matcher, "w ")), vec!["btw"]);
+
+        type_text(&mut matcher, "xbtw");
+        for _ in 0..3 {
+            press(&mut matcher, VKey::Back, &mut state);
+        }
+        assert!(
+            type_text(&mut matcher, "btw ").is_empty(),
+            "`btw` after an `x`"
+        );
+    }
+
+    #[test]
+    fn test_reset_keys() {
+        let mut matcher = matcher(vec![Hotstring::new("btw", || {})]);
+
+        type_text(&mut matcher, "bt");
+        press(&mut matcher, VKey::Left, &mut KeyboardState::new());
+        assert!(type_text(&mut matcher, "w ").is_empty());
+
+        type_text(&mut matcher, "bt");
+        let mut state = KeyboardState::new();
+        state.keydown(VKey::LControl);
+        press(&mut matcher, VKey::V, &mut state);
+        assert!(type_text(&mut matcher, "w ").is_empty(), "Shortcut resets");
+
+        type_text(&mut matcher, "bt");
+        let mut state = KeyboardState::new();
+        press(&mut matcher, VKey::LShift, &mut state);
+        press(&mut matcher, VKey::Capital, &mut state);
+        assert_eq!(
+            triggers(&type_text(&mut matcher, "w ")),
+            vec!["btw"],
+            "Modifiers and toggles don't reset"
+        );
+
+        type_text(&mut matcher, "bt");
+        matcher.reset();
+        assert!(type_text(&mut matcher, "w ").is_empty());
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut matcher = matcher(vec![
+            Hotstring::new("btw", || {}),
+            Hotstring::new("omw", || {}).bypass_pause(),
+        ]);
+        let state = KeyboardState::new();
+        let now = Instant::now();
+
+        for key in [
+            VKey::B,
+            VKey::T,
+            VKey::W,
+            VKey::Space,
+            VKey::O,
+            VKey::M,
+            VKey::W,
+        ] {
+            assert!(matcher.key_down(key, &state, true, now).is_none());
+        }
+        let (_, event) = matcher.key_down(VKey::Space, &state, true, now).unwrap();
+        assert_eq!(event.trigger, "omw");
+    }
+
+    #[test]
+    fn test_registration() {
+        let mut matcher = HotstringMatcher::new();
+        assert!(matches!(
+            matcher.register(Hotstring::new("", || {})),
+            Err(WHKError::InvalidHotstring(_))
+        ));
+
+        let id = matcher.register(Hotstring::new("btw", || {})).unwrap();
+        assert!(matches!(
+            matcher.register(Hotstring::new("BTW", || {})),
+            Err(WHKError::HotstringAlreadyRegistered(_))
+        ));
+        assert!(matcher
+            .register(Hotstring::new("BTW", || {}).case_sensitive())
+            .is_ok());
+
+        assert!(matcher.unregister(id).is_some());
+        assert!(type_text(&mut matcher, "btw ").is_empty());
+    }
+
+    #[test]
+    fn test_expansion() {
+        let sink = RecordingSink::new();
+        let output = TextOutput::new(sink.clone());
+        let mut matcher = matcher(vec![Hotstring::expansion_with(
+            "btw",
+            TextTemplate::text("by the way"),
+            output.clone(),
+        )]);
+
+        let mut state = KeyboardState::new();
+        for key in [VKey::B, VKey::T, VKey::W] {
+            press(&mut matcher, key, &mut state);
+        }
+        state.keydown(VKey::OemComma);
+        let (callback, event) = matcher
+            .key_down(VKey::OemComma, &state, false, Instant::now())
+            .unwrap();
+        callback(&event);
+
+        let expected = RecordingSink::new();
+        let erase_and_type = TextOutput::new(expected.clone());
+        erase_and_type.send_backspaces(4).unwrap();
+        erase_and_type.send_text("by the way,").unwrap();
+        assert_eq!(sink.events(), expected.events());
+    }
+}
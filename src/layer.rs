@@ -0,0 +1,223 @@
+//! Layers of hotkeys, e.g. a vim-like "navigation" mode.
+//!
+//! Hotkeys assigned to a named layer, see [`Hotkey::layer`], only trigger while
+//! the layer is active. Active layers form a stack on top of the base layer of
+//! the hotkeys without a layer, and matching searches from the top of the stack
+//! down. Layers are switched by hotkeys with a [`LayerAction`], or by the manager.
+//!
+//! [`Hotkey::layer`]: crate::Hotkey::layer
+
+use crate::state::KeyboardState;
+use crate::VKey;
+
+/// Changes the active layers when a hotkey triggers, see [`crate::Hotkey::switch_layer`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LayerAction {
+    /// Activates the layer on top of the stack.
+    Push(String),
+    /// Deactivates the layer on top of the stack.
+    Pop,
+    /// Deactivates the layer if active, activates it on top of the stack otherwise.
+    Toggle(String),
+    /// Activates the layer on top of the stack while the hotkey is held.
+    Momentary(String),
+}
+
+/// Layer switch of a hotkey call, with the keys holding a momentary layer.
+#[derive(Debug, Clone)]
+pub(crate) struct LayerSwitch {
+    pub action: LayerAction,
+    pub combo: Vec<VKey>,
+}
+
+/// Notification of a change of the active layers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerChange {
+    /// The layers active before the change, from the bottom of the stack.
+    pub previous: Vec<String>,
+    /// The layers active after the change, from the bottom of the stack.
+    pub active: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Layer {
+    name: String,
+    /// keys holding a momentary layer
+    held_by: Option<Vec<VKey>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct LayerStack {
+    layers: Vec<Layer>,
+}
+
+impl LayerStack {
+    pub const fn new() -> Self {
+        Self { layers: Vec::new() }
+    }
+
+    /// The active layers, from the bottom of the stack.
+    pub fn active(&self) -> Vec<String> {
+        self.layers.iter().map(|layer| layer.name.clone()).collect()
+    }
+
+    /// Activates `name` on top of the stack, moving it there if already active.
+    pub fn push(&mut self, name: &str) -> Option<LayerChange> {
+        self.change(|layers| {
+            layers.retain(|layer| layer.name != name);
+            layers.push(Layer {
+                name: name.to_owned(),
+                held_by: None,
+            });
+        })
+    }
+
+    pub fn pop(&mut self) -> Option<LayerChange> {
+        self.change(|layers| {
+            layers.pop();
+        })
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<LayerChange> {
+        self.change(|layers| layers.retain(|layer| layer.name != name))
+    }
+
+    pub fn toggle(&mut self, name: &str) -> Option<LayerChange> {
+        if self.layers.iter().any(|layer| layer.name == name) {
+            self.remove(name)
+        } else {
+            self.push(name)
+        }
+    }
+
+    pub fn clear(&mut self) -> Option<LayerChange> {
+        self.change(Vec::clear)
+    }
+
+    /// Applies the layer switch of a triggered hotkey.
+    pub fn apply(&mut self, switch: &LayerSwitch) -> Option<LayerChange> {
+        match &switch.action {
+            LayerAction::Push(name) => self.push(name),
+            LayerAction::Pop => self.pop(),
+            LayerAction::Toggle(name) => self.toggle(name),
+            LayerAction::Momentary(name) => {
+                if self.layers.iter().any(|layer| layer.name == *name) {
+                    // auto-repeat of the hotkey, or already active
+                    return None;
+                }
+                self.change(|layers| {
+                    layers.push(Layer {
+                        name: name.clone(),
+                        held_by: Some(switch.combo.clone()),
+                    })
+                })
+            }
+        }
+    }
+
+    /// Deactivates the momentary layers whose keys are no longer held in `state`.
+    pub fn released(&mut self, state: &KeyboardState) -> Option<LayerChange> {
+        self.change(|layers| {
+            layers.retain(|layer| {
+                layer
+                    .held_by
+                    .as_ref()
+                    .is_none_or(|combo| combo.iter().all(|key| state.is_down(*key)))
+            })
+        })
+    }
+
+    /// Applies `update`, returns the change of the active layers if any.
+    fn change<F: FnOnce(&mut Vec<Layer>)>(&mut self, update: F) -> Option<LayerChange> {
+        let previous = self.active();
+        update(&mut self.layers);
+        let active = self.active();
+        (previous != active).then_some(LayerChange { previous, active })
+    }
+}
+
+/// Returns the depth of `layer` in the `active` layers, `0` for the base layer,
+/// or `None` if the layer is not active.
+pub(crate) fn depth(active: &[String], layer: Option<&str>) -> Option<usize> {
+    match layer {
+        None => Some(0),
+        Some(name) => active
+            .iter()
+            .position(|active| active == name)
+            .map(|position| position + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn momentary(name: &str, combo: &[VKey]) -> LayerSwitch {
+        LayerSwitch {
+            action: LayerAction::Momentary(name.to_owned()),
+            combo: combo.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_stack() {
+        let mut stack = LayerStack::new();
+        assert_eq!(
+            stack.push("nav"),
+            Some(LayerChange {
+                previous: vec![],
+                active: vec!["nav".to_owned()],
+            })
+        );
+        stack.push("symbols");
+        stack.push("nav");
+        assert_eq!(stack.active(), ["symbols", "nav"], "Moved to the top");
+
+        assert!(stack.pop().is_some());
+        assert_eq!(stack.active(), ["symbols"]);
+        assert!(stack.toggle("symbols").is_some());
+        assert!(stack.pop().is_none(), "No change, no notification");
+    }
+
+    #[test]
+    fn test_momentary() {
+        let mut stack = LayerStack::new();
+        let switch = momentary("fn", &[VKey::Space, VKey::Control]);
+        assert!(stack.apply(&switch).is_some());
+        assert!(stack.apply(&switch).is_none(), "Auto-repeat");
+
+        let mut state = KeyboardState::new();
+        state.keydown(VKey::RControl);
+        state.keydown(VKey::Space);
+        assert!(stack.released(&state).is_none());
+
+        state.keyup(VKey::RControl);
+        assert_eq!(
+            stack.released(&state),
+            Some(LayerChange {
+                previous: vec!["fn".to_owned()],
+                active: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn test_momentary_keeps_other_layers() {
+        let mut stack = LayerStack::new();
+        stack.push("nav");
+        stack.apply(&momentary("fn", &[VKey::F24]));
+        stack.push("symbols");
+
+        stack.released(&KeyboardState::new());
+        assert_eq!(stack.active(), ["nav", "symbols"]);
+    }
+
+    #[test]
+    fn test_depth() {
+        let active = vec!["nav".to_owned(), "symbols".to_owned()];
+        assert_eq!(depth(&active, None), Some(0));
+        assert_eq!(depth(&active, Some("symbols")), Some(2));
+        assert_eq!(depth(&active, Some("media")), None);
+    }
+}
@@ -5,16 +5,46 @@
 //! and handling keyboard events in a safe and efficient manner.
 #![cfg(windows)]
 
+mod activation;
 mod client_executor;
+pub mod clock;
+mod conflicts;
+mod cooldown;
+mod decision;
 pub mod error;
 pub mod events;
+mod group;
+mod held_repeat;
+mod hold;
 pub mod hook;
 mod hotkey;
+mod hotstring;
 mod keys;
+mod layer;
+pub mod macros;
 mod manager;
+mod modifier_tap;
+pub mod output;
+mod pause;
+mod profile;
+mod registry;
+mod remap;
+mod shortcut_recorder;
 pub mod state;
+mod steal;
+mod taps;
+mod timer;
+mod usage;
 mod utils;
 
+pub use conflicts::*;
+pub use group::{GroupId, HotkeyGroup};
 pub use hotkey::*;
+pub use hotstring::{Hotstring, HotstringEvent, HotstringId, END_CHARS};
 pub use keys::*;
+pub use layer::{LayerAction, LayerChange};
 pub use manager::*;
+pub use pause::{PauseChange, PauseGuard};
+pub use profile::HotkeyProfile;
+pub use shortcut_recorder::{ShortcutFuture, ShortcutRecorder};
+pub use steal::{FreeReason, StealOptions};
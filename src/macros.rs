@@ -0,0 +1,556 @@
+//! Keyboard macros: key sequences recorded with their timing and replayed later.
+//!
+//! The [`MacroRecorder`] turns keyboard events into a [`Macro`], which is
+//! serializable with the `serde` feature. The [`MacroPlayer`] injects a macro
+//! through an [`InputSink`], the injected keys don't trigger hotkeys.
+//!
+//! Recording and playback take the current instant as a parameter, so both can
+//! be driven by a [`crate::clock::ManualClock`] on tests.
+
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{RecvTimeoutError, Sender};
+
+use crate::clock;
+use crate::error::Result;
+use crate::events::KeyboardInputEvent;
+use crate::output::{InputEvent, InputSink, SendInputSink};
+use crate::state::{KeyboardState, KEYBOARD_STATE};
+use crate::VKey;
+
+/// Modifier keys released before playback, see [`MacroPlayer::release_modifiers_first`].
+const MODIFIERS: [VKey; 8] = [
+    VKey::LShift,
+    VKey::RShift,
+    VKey::LControl,
+    VKey::RControl,
+    VKey::LMenu,
+    VKey::RMenu,
+    VKey::LWin,
+    VKey::RWin,
+];
+
+/// A key press or release of a macro.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MacroStep {
+    /// Time elapsed since the previous step, or since the start of the macro.
+    pub delay: Duration,
+    pub key: VKey,
+    pub down: bool,
+}
+
+/// A recorded key sequence.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Macro {
+    pub steps: Vec<MacroStep>,
+}
+
+impl Macro {
+    /// Returns the duration of the macro at normal speed.
+    pub fn duration(&self) -> Duration {
+        self.steps.iter().map(|step| step.delay).sum()
+    }
+}
+
+/// Records the keyboard events between [`MacroRecorder::start`] and [`MacroRecorder::stop`].
+#[derive(Debug, Default)]
+pub struct MacroRecorder {
+    /// start of the recording, or time of the last recorded step
+    last: Option<Instant>,
+    steps: Vec<MacroStep>,
+}
+
+impl MacroRecorder {
+    pub const fn new() -> Self {
+        Self {
+            last: None,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Starts a new recording, discarding the current one.
+    pub fn start(&mut self, now: Instant) {
+        self.last = Some(now);
+        self.steps.clear();
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.last.is_some()
+    }
+
+    /// Records an event, ignored if not recording.
+    pub fn record(&mut self, event: &KeyboardInputEvent, now: Instant) {
+        let Some(last) = self.last else {
+            return;
+        };
+        let (key, down) = match event {
+            KeyboardInputEvent::KeyDown { key, .. } => (*key, true),
+            KeyboardInputEvent::KeyUp { key, .. } => (*key, false),
+        };
+        self.steps.push(MacroStep {
+            delay: now.saturating_duration_since(last),
+            key,
+            down,
+        });
+        self.last = Some(now);
+    }
+
+    /// Stops the recording and returns the macro.
+    ///
+    /// Only complete key presses are kept: keys already held when the recording
+    /// started, like the keys of the hotkey starting it, have their release
+    /// dropped, and keys still held when it stops have their press dropped.
+    pub fn stop(&mut self) -> Macro {
+        self.last = None;
+        let steps = std::mem::take(&mut self.steps);
+
+        let mut kept = vec![true; steps.len()];
+        let mut held: Vec<VKey> = Vec::new();
+        for (i, step) in steps.iter().enumerate() {
+            let pressed = held.iter().position(|key| *key == step.key);
+            match (step.down, pressed) {
+                (true, None) => held.push(step.key),
+                (true, Some(_)) => {}
+                (false, Some(position)) => {
+                    held.remove(position);
+                }
+                (false, None) => kept[i] = false,
+            }
+        }
+        for key in held {
+            for (i, step) in steps.iter().enumerate() {
+                if step.key == key {
+                    kept[i] = false;
+                }
+            }
+        }
+
+        // the delays of the dropped steps go to the next kept step
+        let mut macro_steps = Vec::new();
+        let mut delay = Duration::ZERO;
+        for (step, kept) in steps.into_iter().zip(kept) {
+            delay += step.delay;
+            if kept {
+                macro_steps.push(MacroStep { delay, ..step });
+                delay = Duration::ZERO;
+            }
+        }
+        Macro { steps: macro_steps }
+    }
+}
+
+/// Replays macros through an [`InputSink`].
+#[derive(Clone)]
+pub struct MacroPlayer {
+    sink: Arc<dyn InputSink>,
+    speed: f64,
+    release_modifiers: bool,
+}
+
+impl MacroPlayer {
+    pub fn new<S: InputSink + 'static>(sink: S) -> Self {
+        Self {
+            sink: Arc::new(sink),
+            speed: 1.0,
+            release_modifiers: false,
+        }
+    }
+
+    /// Scales the playback speed, `2.0` plays twice as fast, `0.0` without any delay.
+    pub fn speed(mut self, speed: f64) -> Self {
+        self.speed = if speed > 0.0 { speed } else { f64::INFINITY };
+        self
+    }
+
+    /// Releases the modifiers held on playback start, e.g. the modifiers of the
+    /// hotkey starting the playback, so they don't alter the replayed keys.
+    pub fn release_modifiers_first(mut self) -> Self {
+        self.release_modifiers = true;
+        self
+    }
+
+    /// Starts a playback driven by [`Playback::poll`], `state` is the keyboard
+    /// state used to release the held modifiers.
+    pub fn playback(
+        &self,
+        macro_: &Macro,
+        state: &KeyboardState,
+        now: Instant,
+    ) -> Result<Playback> {
+        if self.release_modifiers {
+            let releases: Vec<InputEvent> = MODIFIERS
+                .iter()
+                .filter(|key| state.pressing.contains(key))
+                .map(|key| InputEvent::Key {
+                    key: *key,
+                    down: false,
+                })
+                .collect();
+            if !releases.is_empty() {
+                self.sink.send(&releases)?;
+            }
+        }
+
+        let mut offset = Duration::ZERO;
+        let steps = macro_
+            .steps
+            .iter()
+            .map(|step| {
+                offset += step.delay;
+                let due = if self.speed.is_finite() {
+                    offset.div_f64(self.speed)
+                } else {
+                    Duration::ZERO
+                };
+                (now + due, *step)
+            })
+            .collect();
+        Ok(Playback {
+            sink: self.sink.clone(),
+            steps,
+            next: 0,
+            held: Vec::new(),
+        })
+    }
+
+    /// Plays a macro on a new thread, with the current keyboard state and clock.
+    pub fn play(&self, macro_: &Macro) -> Result<PlaybackHandle> {
+        let state = KEYBOARD_STATE.lock()?.clone();
+        let mut playback = self.playback(macro_, &state, clock::now())?;
+        let (cancel, cancelled) = crossbeam_channel::bounded::<()>(1);
+
+        let thread = std::thread::spawn(move || {
+            while let Some(deadline) = playback.deadline() {
+                let wait = deadline.saturating_duration_since(clock::now());
+                match cancelled.recv_timeout(wait) {
+                    Ok(()) => return playback.cancel(),
+                    Err(RecvTimeoutError::Timeout) => {}
+                    // the handle was dropped, the playback goes on
+                    Err(RecvTimeoutError::Disconnected) => std::thread::sleep(wait),
+                }
+                playback.poll(clock::now())?;
+            }
+            Ok(())
+        });
+        Ok(PlaybackHandle { cancel, thread })
+    }
+}
+
+impl Default for MacroPlayer {
+    fn default() -> Self {
+        MacroPlayer::new(SendInputSink)
+    }
+}
+
+/// An ongoing playback of a macro.
+pub struct Playback {
+    sink: Arc<dyn InputSink>,
+    steps: Vec<(Instant, MacroStep)>,
+    next: usize,
+    /// keys pressed by the playback and not released yet
+    held: Vec<VKey>,
+}
+
+impl Playback {
+    /// Deadline of the next step, `None` once the playback is finished.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.steps.get(self.next).map(|(due, _)| *due)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.steps.len()
+    }
+
+    /// Sends the steps due at `now`.
+    pub fn poll(&mut self, now: Instant) -> Result<()> {
+        let due = self.steps[self.next..]
+            .iter()
+            .take_while(|(due, _)| *due <= now)
+            .count();
+        let events: Vec<InputEvent> = self.steps[self.next..self.next + due]
+            .iter()
+            .map(|(_, step)| InputEvent::Key {
+                key: step.key,
+                down: step.down,
+            })
+            .collect();
+        self.next += due;
+        if events.is_empty() {
+            return Ok(());
+        }
+        for event in &events {
+            if let InputEvent::Key { key, down } = *event {
+                self.held.retain(|held| *held != key);
+                if down {
+                    self.held.push(key);
+                }
+            }
+        }
+        self.sink.send(&events)
+    }
+
+    /// Stops the playback, releasing the keys it holds.
+    pub fn cancel(&mut self) -> Result<()> {
+        self.next = self.steps.len();
+        let releases: Vec<InputEvent> = std::mem::take(&mut self.held)
+            .into_iter()
+            .rev()
+            .map(|key| InputEvent::Key { key, down: false })
+            .collect();
+        if releases.is_empty() {
+            return Ok(());
+        }
+        self.sink.send(&releases)
+    }
+}
+
+/// Controls a playback started with [`MacroPlayer::play`].
+#[derive(Debug)]
+pub struct PlaybackHandle {
+    cancel: Sender<()>,
+    thread: JoinHandle<Result<()>>,
+}
+
+impl PlaybackHandle {
+    /// Stops the playback, the keys it holds are released.
+    pub fn cancel(&self) {
+        let _ = self.cancel.try_send(());
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Waits for the end of the playback.
+    pub fn join(self) -> Result<()> {
+        self.thread.join().unwrap_or(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+    use crate::output::RecordingSink;
+
+    fn key_down(key: VKey) -> KeyboardInputEvent {
+        KeyboardInputEvent::KeyDown {
+            key,
+            state: KeyboardState::new(),
+        }
+    }
+
+    fn key_up(key: VKey) -> KeyboardInputEvent {
+        KeyboardInputEvent::KeyUp {
+            key,
+            state: KeyboardState::new(),
+        }
+    }
+
+    fn step(millis: u64, key: VKey, down: bool) -> MacroStep {
+        MacroStep {
+            delay: Duration::from_millis(millis),
+            key,
+            down,
+        }
+    }
+
+    fn event(key: VKey, down: bool) -> InputEvent {
+        InputEvent::Key { key, down }
+    }
+
+    /// Types `A` then `B`, a press every 100ms.
+    fn macro_ab() -> Macro {
+        Macro {
+            steps: vec![
+                step(0, VKey::A, true),
+                step(50, VKey::A, false),
+                step(50, VKey::B, true),
+                step(50, VKey::B, false),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_recording() {
+        let clock = ManualClock::new();
+        let mut recorder = MacroRecorder::new();
+
+        recorder.record(&key_down(VKey::Z), clock.now());
+        recorder.start(clock.now());
+        assert!(recorder.is_recording());
+        for (event, millis) in [
+            (key_down(VKey::A), 0),
+            (key_up(VKey::A), 50),
+            (key_down(VKey::B), 50),
+            (key_up(VKey::B), 50),
+        ] {
+            clock.advance(Duration::from_millis(millis));
+            recorder.record(&event, clock.now());
+        }
+
+        assert_eq!(recorder.stop(), macro_ab());
+        assert!(!recorder.is_recording());
+        recorder.record(&key_down(VKey::Z), clock.now());
+        assert!(recorder.stop().steps.is_empty());
+    }
+
+    #[test]
+    fn test_recording_keeps_complete_presses() {
+        let clock = ManualClock::new();
+        let mut recorder = MacroRecorder::new();
+        recorder.start(clock.now());
+
+        for (event, millis) in [
+            // release of the hotkey starting the recording
+            (key_up(VKey::F9), 10),
+            (key_down(VKey::A), 40),
+            (key_up(VKey::A), 50),
+            // press of the hotkey stopping the recording
+            (key_down(VKey::F10), 20),
+            (key_down(VKey::B), 30),
+            (key_up(VKey::B), 50),
+        ] {
+            clock.advance(Duration::from_millis(millis));
+            recorder.record(&event, clock.now());
+        }
+
+        assert_eq!(
+            recorder.stop(),
+            Macro {
+                steps: vec![
+                    step(50, VKey::A, true),
+                    step(50, VKey::A, false),
+                    step(50, VKey::B, true),
+                    step(50, VKey::B, false),
+                ],
+            },
+            "Delays of the dropped steps are kept"
+        );
+    }
+
+    #[test]
+    fn test_playback() {
+        let clock = ManualClock::new();
+        let sink = RecordingSink::new();
+        let player = MacroPlayer::new(sink.clone());
+        let mut playback = player
+            .playback(&macro_ab(), &KeyboardState::new(), clock.now())
+            .unwrap();
+
+        playback.poll(clock.now()).unwrap();
+        assert_eq!(sink.events(), vec![event(VKey::A, true)]);
+
+        clock.advance(Duration::from_millis(120));
+        playback.poll(clock.now()).unwrap();
+        assert_eq!(
+            sink.batches()[1],
+            vec![event(VKey::A, false), event(VKey::B, true)]
+        );
+        assert_eq!(
+            playback.deadline(),
+            Some(clock.now() + Duration::from_millis(30))
+        );
+
+        clock.advance(Duration::from_millis(30));
+        playback.poll(clock.now()).unwrap();
+        assert!(playback.is_finished());
+        assert_eq!(sink.events().len(), 4);
+    }
+
+    #[test]
+    fn test_speed() {
+        let clock = ManualClock::new();
+        let sink = RecordingSink::new();
+        let start = clock.now();
+
+        let playback = MacroPlayer::new(sink.clone())
+            .speed(2.0)
+            .playback(&macro_ab(), &KeyboardState::new(), start)
+            .unwrap();
+        let deadlines: Vec<Duration> = playback.steps.iter().map(|(due, _)| *due - start).collect();
+        assert_eq!(
+            deadlines,
+            [0, 25, 50, 75].map(Duration::from_millis).to_vec()
+        );
+
+        let playback = MacroPlayer::new(sink)
+            .speed(0.0)
+            .playback(&macro_ab(), &KeyboardState::new(), start)
+            .unwrap();
+        assert!(playback.steps.iter().all(|(due, _)| *due == start));
+    }
+
+    #[test]
+    fn test_release_modifiers_first() {
+        let clock = ManualClock::new();
+        let sink = RecordingSink::new();
+        let mut state = KeyboardState::new();
+        state.keydown(VKey::LControl);
+        state.keydown(VKey::F9);
+
+        MacroPlayer::new(sink.clone())
+            .release_modifiers_first()
+            .playback(&macro_ab(), &state, clock.now())
+            .unwrap();
+        assert_eq!(sink.batches(), vec![vec![event(VKey::LControl, false)]]);
+    }
+
+    #[test]
+    fn test_cancel_releases_held_keys() {
+        let clock = ManualClock::new();
+        let sink = RecordingSink::new();
+        let mut playback = MacroPlayer::new(sink.clone())
+            .playback(
+                &Macro {
+                    steps: vec![
+                        step(0, VKey::LShift, true),
+                        step(0, VKey::A, true),
+                        step(50, VKey::A, false),
+                        step(0, VKey::LShift, false),
+                    ],
+                },
+                &KeyboardState::new(),
+                clock.now(),
+            )
+            .unwrap();
+
+        playback.poll(clock.now()).unwrap();
+        playback.cancel().unwrap();
+        assert!(playback.is_finished());
+        assert_eq!(
+            sink.batches()[1],
+            vec![event(VKey::A, false), event(VKey::LShift, false)]
+        );
+
+        clock.advance(Duration::from_millis(50));
+        playback.poll(clock.now()).unwrap();
+        assert_eq!(sink.batches().len(), 2, "Nothing is sent after cancel");
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let macro_ = Macro {
+            steps: vec![MacroStep {
+                delay: Duration::from_millis(250),
+                key: VKey::A,
+                down: true,
+            }],
+        };
+        let json = serde_json::to_string(&macro_).unwrap();
+        assert_eq!(
+            json,
+            r#"{"steps":[{"delay":{"secs":0,"nanos":250000000},"key":"A","down":true}]}"#
+        );
+        assert_eq!(serde_json::from_str::<Macro>(&json).unwrap(), macro_);
+    }
+}
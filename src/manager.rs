@@ -4,32 +4,117 @@
 
 use arc_swap::ArcSwapOption;
 
-use crate::client_executor::{self, run_on_executor_thread};
-use crate::error::WHKError::HotKeyAlreadyRegistered;
-use crate::error::{Result, WHKError};
-use crate::events::{EventLoopEvent, KeyAction, KeyboardInputEvent};
-use crate::hotkey::{Hotkey, TriggerBehavior, TriggerTiming};
-use crate::state::KEYBOARD_STATE;
+use crossbeam_channel::RecvTimeoutError;
+
+use crate::activation::Activations;
+use crate::client_executor::{
+    self, run_hotkey_on_executor_thread, run_on_executor_thread, HotkeyCall,
+};
+use crate::clock::{self, Clock};
+use crate::conflicts::HotkeySpec;
+use crate::cooldown::CooldownTracker;
+use crate::decision;
+use crate::error::{Result, ShortcutError, WHKError};
+use crate::events::{EventLoopEvent, KeyAction, KeyboardInputEvent, RepeatCounter};
+use crate::group::PAUSED_GROUPS;
+use crate::held_repeat::HeldRepeat;
+use crate::hold::PendingPress;
+use crate::hotkey::{Hotkey, HotkeyId, HotkeyMetadata, TriggerBehavior, TriggerTiming};
+use crate::hotstring::{Hotstring, HotstringId, HotstringMatcher};
+use crate::layer::{self, LayerChange, LayerStack};
+use crate::macros::{Macro, MacroRecorder};
+use crate::modifier_tap::ModifierTapTracker;
+use crate::pause::{PauseChange, PauseGuard, PauseState};
+use crate::profile::{HotkeyProfile, Profiles};
+use crate::registry::HotkeyRegistry;
+use crate::remap::REMAPS;
+use crate::shortcut_recorder::{ShortcutFuture, ShortcutRecorder};
+use crate::state::{KeyboardState, KEYBOARD_STATE};
+use crate::steal::{FreeReason, StealOptions};
+use crate::taps::{TapDecision, TapTracker};
+use crate::timer::{TimerEvent, TIMERS};
+use crate::usage::Usage;
 use crate::VKey;
 use crate::{hook, log_on_dev};
-use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
 
-type HotkeysMap = Arc<Mutex<HashMap<VKey, HashSet<Hotkey>>>>;
+type HotkeysMap = Arc<Mutex<HotkeyRegistry>>;
 type KeyboardCallback = dyn Fn(KeyboardInputEvent) + Send + Sync + 'static;
-type FreeKeyboardCallback = dyn Fn() + Send + Sync + 'static;
+type FreeKeyboardCallback = dyn Fn(FreeReason) + Send + Sync + 'static;
+type LayerChangeCallback = dyn Fn(&LayerChange) + Send + Sync + 'static;
+type PauseCallback = dyn Fn(&PauseChange) + Send + Sync + 'static;
+
+static HOTKEYS: LazyLock<HotkeysMap> = LazyLock::new(|| {
+    Arc::new(Mutex::new(HotkeyRegistry::with_hotkeys(
+        HotkeyManager::get_initial_hotkeys(),
+    )))
+});
+
+static TAP_TRACKER: LazyLock<Mutex<TapTracker>> =
+    LazyLock::new(|| Mutex::new(TapTracker::default()));
+static REPEAT_COUNTER: LazyLock<Mutex<RepeatCounter>> =
+    LazyLock::new(|| Mutex::new(RepeatCounter::default()));
+
+static COOLDOWNS: Mutex<CooldownTracker> = Mutex::new(CooldownTracker::new());
+static MODIFIER_TAP_TRACKER: Mutex<ModifierTapTracker> = Mutex::new(ModifierTapTracker::new());
+static PENDING_PRESS: Mutex<Option<PendingPress<PendingCall>>> = Mutex::new(None);
+
+/// Call of a hold or tap hotkey, with the action taken on the key if it is cooling down.
+type PendingCall = (HotkeyCall, KeyAction);
+static ACTIVATIONS: Mutex<Activations<HotkeyCall>> = Mutex::new(Activations::new());
+static HELD_REPEATS: Mutex<Vec<HeldRepeat<RepeatAction>>> = Mutex::new(Vec::new());
+static HOTSTRINGS: Mutex<HotstringMatcher> = Mutex::new(HotstringMatcher::new());
+static MACRO_RECORDER: Mutex<MacroRecorder> = Mutex::new(MacroRecorder::new());
+static SHORTCUT_RECORDING: Mutex<Option<ShortcutRecording>> = Mutex::new(None);
+static LAYERS: Mutex<LayerStack> = Mutex::new(LayerStack::new());
+static PROFILES: Mutex<Profiles> = Mutex::new(Profiles::new());
+
+/// The call made on each repeat of a hotkey held down.
+struct RepeatAction {
+    call: HotkeyCall,
+    bypass_pause: bool,
+}
 
-static HOTKEYS: LazyLock<HotkeysMap> =
-    LazyLock::new(|| Arc::new(Mutex::new(HotkeyManager::get_initial_hotkeys())));
+/// A shortcut being recorded, see [`HotkeyManager::record_shortcut`].
+struct ShortcutRecording {
+    recorder: ShortcutRecorder,
+    outcome: crossbeam_channel::Sender<Result<HotkeySpec, ShortcutError>>,
+}
+
+static MATCH_POLICY: Mutex<MatchPolicy> = Mutex::new(MatchPolicy::FirstMatch);
 
 static PAUSED: AtomicBool = AtomicBool::new(false);
+static PAUSE_STATE: Mutex<PauseState> = Mutex::new(PauseState::new());
 static STEALING: AtomicBool = AtomicBool::new(false);
+static STEAL_OPTIONS: Mutex<Option<StealOptions>> = Mutex::new(None);
+/// Keys whose press was blocked by the stealing mode, only their releases are blocked.
+static STOLEN_KEYS: Mutex<Vec<VKey>> = Mutex::new(Vec::new());
 
 static CLIENT_KEYBOARD_CALLBACK: ArcSwapOption<Box<KeyboardCallback>> =
     ArcSwapOption::const_empty();
 static CLIENT_ON_FREE_KEYBOARD_CB: ArcSwapOption<Box<FreeKeyboardCallback>> =
     ArcSwapOption::const_empty();
+static CLIENT_LAYER_CHANGE_CB: ArcSwapOption<Box<LayerChangeCallback>> =
+    ArcSwapOption::const_empty();
+static CLIENT_PAUSE_CB: ArcSwapOption<Box<PauseCallback>> = ArcSwapOption::const_empty();
+
+/// Defines which hotkeys run when several registered hotkeys match the same event.
+///
+/// Matching hotkeys are always considered in the same order: the hotkeys of the layer
+/// on top of the active layers first, down to the base layer, then higher
+/// [`Hotkey::priority`] first, then the most specific hotkey (strictest [`crate::ModifierMatch`], then
+/// strict sequence, then the most modifiers), then registration order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatchPolicy {
+    /// Only the first matching hotkey runs, its behavior decides what happens with the key
+    #[default]
+    FirstMatch,
+    /// Every matching hotkey runs in order, the key is blocked if any of them stops propagation
+    AllMatches,
+}
 
 /// Manages the hotkeys, including their registration, unregistration, and execution.
 ///
@@ -39,7 +124,7 @@ static CLIENT_ON_FREE_KEYBOARD_CB: ArcSwapOption<Box<FreeKeyboardCallback>> =
 #[allow(dead_code)]
 pub struct HotkeyManager {
     /// stores the registered hotkeys
-    hotkeys: HotkeysMap,
+    pub(crate) hotkeys: HotkeysMap,
     /// indicates whether the hotkey manager is paused
     paused: &'static AtomicBool,
     /// indicates whether the hotkey manager is in stealing mode
@@ -65,55 +150,325 @@ impl HotkeyManager {
     pub fn steal_keyboard<F>(&self, on_free: F)
     where
         F: Fn() + Send + Sync + 'static,
+    {
+        self.steal_keyboard_with(StealOptions::default(), move |_| on_free());
+    }
+
+    /// Sets the stealing mode for the hotkey manager, configured by `options`.
+    ///
+    /// Key presses are blocked until an exit combination is pressed, the timeout
+    /// elapses or the client frees the keyboard, `on_free` receives the reason.
+    /// Stealing again replaces the options, the previous `on_free` is not called.
+    pub fn steal_keyboard_with<F>(&self, options: StealOptions, on_free: F)
+    where
+        F: Fn(FreeReason) + Send + Sync + 'static,
     {
         log_on_dev!("Keyboard stealing mode enabled");
-        self.stealing.store(true, Ordering::SeqCst);
+        {
+            let mut timers = TIMERS.lock().unwrap();
+            timers.cancel(|timer| matches!(timer, TimerEvent::StealTimeout));
+            if let Some(timeout) = options.timeout {
+                timers.schedule(clock::now() + timeout, TimerEvent::StealTimeout);
+            }
+        }
+        if options.timeout.is_some() {
+            EventLoopEvent::send(EventLoopEvent::TimersChanged);
+        }
+        *STEAL_OPTIONS.lock().unwrap() = Some(options);
+        STOLEN_KEYS.lock().unwrap().clear();
         CLIENT_ON_FREE_KEYBOARD_CB.store(Some(Arc::new(Box::new(on_free))));
+        self.stealing.store(true, Ordering::SeqCst);
     }
 
     /// Disables the stealing mode for the hotkey manager.
     pub fn free_keyboard(&self) {
-        log_on_dev!("Keyboard stealing mode disabled");
+        self.free_keyboard_for(FreeReason::Programmatic);
+    }
+
+    fn free_keyboard_for(&self, reason: FreeReason) {
+        log_on_dev!("Keyboard stealing mode disabled: {reason:?}");
         self.stealing.store(false, Ordering::SeqCst);
+        *STEAL_OPTIONS.lock().unwrap() = None;
+        TIMERS
+            .lock()
+            .unwrap()
+            .cancel(|timer| matches!(timer, TimerEvent::StealTimeout));
         if let Some(on_free_cb) = CLIENT_ON_FREE_KEYBOARD_CB.swap(None) {
-            run_on_executor_thread(on_free_cb);
+            run_on_executor_thread(Arc::new(move || on_free_cb(reason)));
         }
     }
 
-    /// Registers a new hotkey.
-    pub fn register_hotkey(&self, hotkey: Hotkey) -> Result<u64> {
-        if hotkey.trigger_key == VKey::None {
-            return Err(WHKError::HotkeyInvalidTriggerKey(hotkey.trigger_key));
+    /// Registers a new hotkey, returns its id.
+    pub fn register_hotkey(&self, hotkey: Hotkey) -> Result<HotkeyId> {
+        let mut hotkeys = self.hotkeys.lock()?;
+        let id = hotkeys.register(hotkey, clock::now())?;
+        if let Some(hotkey) = hotkeys.get(id) {
+            if schedule_expiration(hotkey) {
+                EventLoopEvent::send(EventLoopEvent::TimersChanged);
+            }
         }
+        Ok(id)
+    }
 
-        let id = hotkey.as_hash();
-        let was_already_inserted = !self
-            .hotkeys
+    /// Unregisters a hotkey by its unique id.
+    pub fn unregister_hotkey(&self, hotkey_id: HotkeyId) -> Result<()> {
+        self.hotkeys
             .lock()?
-            .entry(hotkey.trigger_key)
-            .or_default()
-            .insert(hotkey);
+            .unregister(hotkey_id)
+            .map(|_| ())
+            .ok_or(WHKError::HotkeyNotFound(hotkey_id))
+    }
 
-        if was_already_inserted {
-            return Err(HotKeyAlreadyRegistered);
+    /// Changes the keys of a registered hotkey, keeping its id, callback and settings.
+    pub fn rebind_hotkey<M: AsRef<[VKey]>>(
+        &self,
+        hotkey_id: HotkeyId,
+        trigger_key: VKey,
+        modifiers: M,
+    ) -> Result<()> {
+        self.hotkeys
+            .lock()?
+            .rebind(hotkey_id, trigger_key, modifiers.as_ref().to_vec())
+    }
+
+    /// Enables or disables a registered hotkey, a disabled hotkey keeps its id and
+    /// callback but never triggers.
+    ///
+    /// Disabling a held hotkey stops its repeats and runs its release callback.
+    pub fn set_enabled(&self, hotkey_id: HotkeyId, enabled: bool) -> Result<()> {
+        {
+            let mut hotkeys = self.hotkeys.lock()?;
+            let hotkey = hotkeys
+                .get_mut(hotkey_id)
+                .ok_or(WHKError::HotkeyNotFound(hotkey_id))?;
+            hotkey.enabled = enabled;
         }
-        Ok(id)
+        if !enabled {
+            HotkeyManager::release_hotkeys(&[hotkey_id]);
+        }
+        Ok(())
     }
 
-    /// Unregisters a hotkey by its unique id.
-    pub fn unregister_hotkey(&self, hotkey_id: u64) -> Result<()> {
-        for hotkeys in self.hotkeys.lock()?.values_mut() {
-            hotkeys.retain(|hotkey| hotkey.as_hash() != hotkey_id);
+    /// Returns whether a registered hotkey is enabled.
+    pub fn is_enabled(&self, hotkey_id: HotkeyId) -> Result<bool> {
+        let hotkeys = self.hotkeys.lock()?;
+        let hotkey = hotkeys
+            .get(hotkey_id)
+            .ok_or(WHKError::HotkeyNotFound(hotkey_id))?;
+        Ok(hotkey.enabled)
+    }
+
+    /// Registers a hotstring, returns its id.
+    pub fn register_hotstring(&self, hotstring: Hotstring) -> Result<HotstringId> {
+        HOTSTRINGS.lock()?.register(hotstring)
+    }
+
+    /// Unregisters a hotstring by its unique id.
+    pub fn unregister_hotstring(&self, hotstring_id: HotstringId) -> Result<()> {
+        HOTSTRINGS.lock()?.unregister(hotstring_id);
+        Ok(())
+    }
+
+    /// Forgets the characters typed so far, e.g. when the focus moves to another field.
+    ///
+    /// The buffer is already reset when another window comes to the foreground.
+    pub fn reset_hotstring_buffer(&self) {
+        HOTSTRINGS.lock().unwrap().reset();
+    }
+
+    /// Records a new shortcut from the user, see [`ShortcutRecorder`], blocking
+    /// until it is recorded or `timeout` elapses.
+    ///
+    /// Key presses are blocked while recording, the event loop must be running.
+    /// It must not be called from a hotkey callback, which would block the next
+    /// callbacks until the timeout, use [`HotkeyManager::record_shortcut_async`] or
+    /// another thread instead.
+    pub fn record_shortcut(&self, timeout: Duration) -> Result<HotkeySpec, ShortcutError> {
+        self.record_shortcut_with(ShortcutRecorder::new(), timeout)
+    }
+
+    /// Records a new shortcut like [`HotkeyManager::record_shortcut`], with a
+    /// configured recorder, it must not be called from a hotkey callback either.
+    pub fn record_shortcut_with(
+        &self,
+        recorder: ShortcutRecorder,
+        timeout: Duration,
+    ) -> Result<HotkeySpec, ShortcutError> {
+        let (outcome, receiver) = crossbeam_channel::bounded(1);
+        {
+            let mut recording = SHORTCUT_RECORDING.lock().unwrap();
+            if recording.is_some() || self.is_stealing_mode() {
+                return Err(ShortcutError::Busy);
+            }
+            *recording = Some(ShortcutRecording { recorder, outcome });
+        }
+        if let Ok(outcome) = receiver.recv_timeout(timeout) {
+            return outcome;
         }
+        SHORTCUT_RECORDING.lock().unwrap().take();
+        // the shortcut may have been recorded right before the recording ended
+        receiver.try_recv().unwrap_or(Err(ShortcutError::TimedOut))
+    }
+
+    /// Records a new shortcut like [`HotkeyManager::record_shortcut`], without blocking.
+    pub fn record_shortcut_async(&self, timeout: Duration) -> ShortcutFuture {
+        ShortcutFuture::spawn(move || HotkeyManager::current().record_shortcut(timeout))
+    }
+
+    /// Starts recording the keyboard events into a macro, discarding the current recording.
+    pub fn start_macro_recording(&self) {
+        MACRO_RECORDER.lock().unwrap().start(clock::now());
+    }
+
+    /// Stops recording and returns the recorded macro, see [`MacroRecorder::stop`].
+    pub fn stop_macro_recording(&self) -> Macro {
+        MACRO_RECORDER.lock().unwrap().stop()
+    }
+
+    pub fn is_recording_macro(&self) -> bool {
+        MACRO_RECORDER.lock().unwrap().is_recording()
+    }
+
+    /// Makes `source` behave as `target`, hotkeys see `target` when `source` is pressed.
+    ///
+    /// A generic `SHIFT`, `CTRL` or `ALT` source, e.g. [`VKey::Control`], remaps both
+    /// sides, the other sources only remap their exact key, e.g. [`VKey::LWin`].
+    /// Changing the remaps doesn't affect the keys already pressed until they are released.
+    pub fn set_remap(&self, source: VKey, target: VKey) -> Result<()> {
+        for key in [source, target] {
+            if key == VKey::None {
+                return Err(WHKError::InvalidRemap(key));
+            }
+        }
+        REMAPS.lock()?.set(source, target);
         Ok(())
     }
 
-    /// Unregisters all hotkeys.
+    /// Removes the remap of `source`.
+    pub fn remove_remap(&self, source: VKey) {
+        REMAPS.lock().unwrap().remove(source);
+    }
+
+    /// Removes all the remaps.
+    pub fn clear_remaps(&self) {
+        REMAPS.lock().unwrap().clear();
+    }
+
+    /// Returns the remaps, as source and target keys.
+    pub fn remaps(&self) -> Vec<(VKey, VKey)> {
+        REMAPS.lock().unwrap().remaps()
+    }
+
+    /// Activates `layer` on top of the active layers, moving it there if already active.
+    pub fn push_layer(&self, layer: &str) {
+        notify_layer_change(LAYERS.lock().unwrap().push(layer));
+    }
+
+    /// Deactivates the layer on top of the active layers.
+    pub fn pop_layer(&self) {
+        notify_layer_change(LAYERS.lock().unwrap().pop());
+    }
+
+    /// Deactivates `layer` if active, activates it on top of the active layers otherwise.
+    pub fn toggle_layer(&self, layer: &str) {
+        notify_layer_change(LAYERS.lock().unwrap().toggle(layer));
+    }
+
+    /// Deactivates `layer` wherever it is in the active layers.
+    pub fn remove_layer(&self, layer: &str) {
+        notify_layer_change(LAYERS.lock().unwrap().remove(layer));
+    }
+
+    /// Deactivates all the layers, only the hotkeys without a layer remain.
+    pub fn clear_layers(&self) {
+        notify_layer_change(LAYERS.lock().unwrap().clear());
+    }
+
+    /// Returns the active layers, from the bottom of the stack.
+    pub fn active_layers(&self) -> Vec<String> {
+        LAYERS.lock().unwrap().active()
+    }
+
+    /// Sets a callback notified on the executor thread when the active layers change,
+    /// e.g. to show the current mode.
+    pub fn set_layer_change_listener<F>(&self, cb: F)
+    where
+        F: Fn(&LayerChange) + Send + Sync + 'static,
+    {
+        CLIENT_LAYER_CHANGE_CB.store(Some(Arc::new(Box::new(cb))));
+    }
+
+    pub fn remove_layer_change_listener(&self) {
+        CLIENT_LAYER_CHANGE_CB.store(None);
+    }
+
+    /// Returns the metadata of a registered hotkey.
+    pub fn hotkey_metadata(&self, hotkey_id: HotkeyId) -> Option<HotkeyMetadata> {
+        let hotkeys = self.hotkeys.lock().ok()?;
+        hotkeys.get(hotkey_id).map(|hotkey| hotkey.metadata.clone())
+    }
+
+    /// Unregisters all hotkeys, the system shortcuts registered by default excepted.
+    ///
+    /// The active profile is deactivated first, so the profiles are kept. The ids
+    /// of the unregistered hotkeys are not allocated again.
     pub fn unregister_all(&mut self) -> Result<()> {
-        *self.hotkeys.lock()? = HotkeyManager::get_initial_hotkeys();
+        let mut profiles = PROFILES.lock()?;
+        let mut hotkeys = self.hotkeys.lock()?;
+        profiles.deactivate(&mut hotkeys);
+        hotkeys.reset(HotkeyManager::get_initial_hotkeys());
+        Ok(())
+    }
+
+    /// Adds a profile to activate later, replacing the profile with the same name.
+    ///
+    /// Replacing the active profile swaps its hotkeys at once.
+    pub fn add_profile(&self, profile: HotkeyProfile) -> Result<()> {
+        let mut profiles = PROFILES.lock()?;
+        let mut hotkeys = self.hotkeys.lock()?;
+        profiles.insert(profile, &mut hotkeys, clock::now())?;
+        schedule_profile_expirations(&profiles, &hotkeys);
         Ok(())
     }
 
+    /// Removes a profile, deactivating it if active, and returns it.
+    pub fn remove_profile(&self, name: &str) -> Option<HotkeyProfile> {
+        let mut profiles = PROFILES.lock().ok()?;
+        let mut hotkeys = self.hotkeys.lock().ok()?;
+        profiles.remove(name, &mut hotkeys)
+    }
+
+    /// Activates a profile in place of the active one, in a single update of the
+    /// registered hotkeys. The global hotkeys stay registered.
+    ///
+    /// The uses and expiration of the profile hotkeys start over on each activation.
+    /// On failure, e.g. if a profile hotkey is already registered globally, the
+    /// active profile stays active.
+    pub fn activate_profile(&self, name: &str) -> Result<()> {
+        let mut profiles = PROFILES.lock()?;
+        let mut hotkeys = self.hotkeys.lock()?;
+        profiles.activate(name, &mut hotkeys, clock::now())?;
+        log_on_dev!("Profile {name} activated");
+        schedule_profile_expirations(&profiles, &hotkeys);
+        Ok(())
+    }
+
+    /// Deactivates the active profile, only the global hotkeys stay registered.
+    pub fn deactivate_profile(&self) {
+        let mut profiles = PROFILES.lock().unwrap();
+        profiles.deactivate(&mut self.hotkeys.lock().unwrap());
+    }
+
+    /// Returns the name of the active profile.
+    pub fn active_profile(&self) -> Option<String> {
+        PROFILES.lock().unwrap().active().map(str::to_owned)
+    }
+
+    /// Returns the names of the profiles, sorted.
+    pub fn profiles(&self) -> Vec<String> {
+        PROFILES.lock().unwrap().names()
+    }
+
     /// Runs the main event loop to listen for keyboard events in a separate thread.
     ///
     /// It matches events against registered hotkeys and executes the corresponding callbacks.
@@ -125,21 +480,74 @@ impl HotkeyManager {
             // clean event loop channel, to remove events before start
             while EventLoopEvent::reciever().try_recv().is_ok() {}
 
-            'event_loop: while let Ok(loop_event) = EventLoopEvent::reciever().recv() {
+            for hotkey in HOTKEYS.lock().unwrap().iter() {
+                schedule_expiration(hotkey);
+            }
+            for deadline in PAUSE_STATE.lock().unwrap().deadlines() {
+                TIMERS
+                    .lock()
+                    .unwrap()
+                    .schedule(deadline, TimerEvent::PauseElapsed);
+            }
+
+            let reciever = EventLoopEvent::reciever();
+            'event_loop: loop {
+                HotkeyManager::process_timers(clock::now());
+
+                let next_timer = TIMERS.lock().unwrap().next_deadline();
+                let loop_event = match next_timer {
+                    Some(deadline) => {
+                        match reciever
+                            .recv_timeout(deadline.saturating_duration_since(clock::now()))
+                        {
+                            Ok(loop_event) => loop_event,
+                            Err(RecvTimeoutError::Timeout) => continue 'event_loop,
+                            Err(RecvTimeoutError::Disconnected) => break 'event_loop,
+                        }
+                    }
+                    None => match reciever.recv() {
+                        Ok(loop_event) => loop_event,
+                        Err(_) => break 'event_loop,
+                    },
+                };
+
                 let event = match loop_event {
                     EventLoopEvent::Stop => break 'event_loop,
                     EventLoopEvent::Keyboard(event) => event,
+                    EventLoopEvent::MouseButtonDown => {
+                        HotkeyManager::process_mouse_event();
+                        continue 'event_loop;
+                    }
+                    EventLoopEvent::ForegroundChanged => {
+                        // the next characters are typed in another window
+                        HOTSTRINGS.lock().unwrap().reset();
+                        HotkeyManager::update_mouse_clicks();
+                        continue 'event_loop;
+                    }
+                    EventLoopEvent::TimersChanged => continue 'event_loop,
                 };
 
                 let key_action = HotkeyManager::process_keyboard_event(event);
                 key_action.send();
+                HotkeyManager::update_mouse_clicks();
             }
+
+            hook::report_mouse_clicks(false);
+            TIMERS.lock().unwrap().clear();
+            TAP_TRACKER.lock().unwrap().clear();
+            *PENDING_PRESS.lock().unwrap() = None;
+            HELD_REPEATS.lock().unwrap().clear();
+            MODIFIER_TAP_TRACKER.lock().unwrap().clear();
+            COOLDOWNS.lock().unwrap().clear();
+            HOTSTRINGS.lock().unwrap().reset();
         });
 
         Ok(handle)
     }
 
     pub(crate) fn process_keyboard_event(event: KeyboardInputEvent) -> KeyAction {
+        MACRO_RECORDER.lock().unwrap().record(&event, clock::now());
+
         if let Some(cb) = CLIENT_KEYBOARD_CALLBACK.load().as_ref() {
             let cb = cb.clone();
             let event = event.clone();
@@ -148,62 +556,678 @@ impl HotkeyManager {
             }));
         }
 
+        if let Some(action) = HotkeyManager::process_shortcut_recording(&event) {
+            return action;
+        }
+
         let manager = HotkeyManager::current();
 
         if manager.is_stealing_mode() {
-            // Stealing mode only affects KeyDown events
-            if let KeyboardInputEvent::KeyDown { key, state: _ } = &event {
-                if key == VKey::Escape {
-                    manager.free_keyboard();
-                }
-                // note: on ESC press we exit stealing mode, but still will block the ESC key
-                return KeyAction::Block;
+            if let Some(action) = HotkeyManager::process_stealing(&event) {
+                return action;
             }
         }
 
+        let repeat = REPEAT_COUNTER.lock().unwrap().observe(&event);
+
         // Extract vk_code, state, and event_type from both KeyDown and KeyUp events
         let (key, state, event_type) = match event {
             KeyboardInputEvent::KeyDown { key, state } => (key, state, TriggerTiming::OnKeyDown),
             KeyboardInputEvent::KeyUp { key, state } => (key, state, TriggerTiming::OnKeyUp),
         };
 
-        let paused_state = HotkeysPauseHandler::current();
+        // a released combination or a resynchronized state ends the activations
+        let released = ACTIVATIONS.lock().unwrap().released(&state);
+        run_releases(released, Some(&state));
+        notify_layer_change(LAYERS.lock().unwrap().released(&state));
+
+        if event_type == TriggerTiming::OnKeyDown {
+            HotkeyManager::process_hotstrings(key, &state);
+        }
+
+        if event_type == TriggerTiming::OnKeyDown && repeat == 0 {
+            TAP_TRACKER.lock().unwrap().interrupt(key);
+            MODIFIER_TAP_TRACKER.lock().unwrap().key_down(
+                key,
+                state.pressing.len() == 1,
+                clock::now(),
+            );
+        }
+
+        if event_type == TriggerTiming::OnKeyUp {
+            HotkeyManager::stop_held_repeats(key);
+            if let Some(action) = HotkeyManager::resolve_modifier_tap(key, &state) {
+                return action;
+            }
+        }
+
+        // presses waiting for a hold or tap outcome are updated before matching
+        let mut replay = None;
+        if let Some(action) = HotkeyManager::update_pending_press(key, event_type, &mut replay) {
+            return action;
+        }
+
+        let action = HotkeyManager::match_hotkeys(key, &state, event_type, repeat);
+
+        let Some(pending_key) = replay else {
+            return action;
+        };
+        // the pending press was blocked without any outcome, so it is sent again
+        // before this event, which has to be blocked and sent again to keep the order.
+        let mut keys = vec![(pending_key, true)];
+        if event_type == TriggerTiming::OnKeyDown {
+            if action == KeyAction::Allow {
+                keys.push((key, true));
+            }
+            hook::send_key_events(&keys);
+            return KeyAction::Block;
+        }
+        // key releases are always sent again, the other applications may have seen the press
+        keys.push((key, false));
+        hook::send_key_events(&keys);
+        KeyAction::Suppress
+    }
+
+    /// Matches the event against the registered hotkeys and runs the callbacks.
+    fn match_hotkeys(
+        key: VKey,
+        state: &KeyboardState,
+        event_type: TriggerTiming,
+        repeat: u32,
+    ) -> KeyAction {
+        let paused = HotkeysPauseHandler::current().is_paused();
+        let paused_groups = PAUSED_GROUPS.lock().unwrap().clone();
+        let layers = LAYERS.lock().unwrap().active();
+        let now = clock::now();
+        let is_candidate = |hotkey: &Hotkey| {
+            // Skip if paused (unless bypass_pause)
+            (!paused || hotkey.bypass_pause)
+                && hotkey.enabled
+                // Skip if its group is paused
+                && hotkey.group.is_none_or(|group| !paused_groups.contains(&group))
+                // Skip if its layer is not active
+                && layer::depth(&layers, hotkey.layer.as_deref()).is_some()
+                // Skip if out of uses or expired
+                && hotkey.usage.is_active(now)
+                // Check if keyboard state matches hotkey
+                && hotkey.is_trigger_state(&key, state)
+        };
+
+        let matched: Vec<HotkeyId> = {
+            let hotkeys = HOTKEYS.lock().unwrap();
+            let matched = resolution_order(
+                hotkeys
+                    .bucket(key)
+                    .iter()
+                    // Skip if timing doesn't match
+                    .filter(|hotkey| hotkey.trigger_timing == event_type && is_candidate(hotkey)),
+                &layers,
+            );
+
+            if matched.iter().any(|hotkey| hotkey.taps > 1) {
+                return HotkeyManager::resolve_taps(&matched, key, state, event_type, repeat);
+            }
+            matched.iter().filter_map(|hotkey| hotkey.id).collect()
+        };
 
-        if let Some(hotkeys) = HOTKEYS.lock().unwrap().get(&key) {
-            for hotkey in hotkeys {
-                // Skip if timing doesn't match
-                if hotkey.trigger_timing != event_type {
-                    continue;
+        let policy = *MATCH_POLICY.lock().unwrap();
+        let mut action = KeyAction::Allow;
+        for id in &matched {
+            // the hotkey declined the event, let the next one have a chance
+            let Some(hotkey_action) = HotkeyManager::run_matched(*id, key, state, repeat) else {
+                continue;
+            };
+            if hotkey_action != KeyAction::Allow && action != KeyAction::Suppress {
+                action = hotkey_action;
+            }
+            if policy == MatchPolicy::FirstMatch {
+                return action;
+            }
+        }
+
+        if !matched.is_empty() {
+            return action;
+        }
+
+        if event_type != TriggerTiming::OnKeyDown || repeat > 0 {
+            return KeyAction::Allow;
+        }
+
+        let hotkeys = HOTKEYS.lock().unwrap();
+        let press_bound: Vec<&Hotkey> = hotkeys
+            .bucket(key)
+            .iter()
+            .filter(|hotkey| {
+                matches!(
+                    hotkey.trigger_timing,
+                    TriggerTiming::OnHold(_) | TriggerTiming::Tap { .. }
+                ) && is_candidate(hotkey)
+            })
+            .collect();
+        HotkeyManager::start_pending_press(&press_bound, key, state)
+    }
+
+    /// Runs a matched hotkey, returns what to do with the key, `None` if the hotkey
+    /// declined the event.
+    ///
+    /// The registry is not locked while the decision callback of the hotkey runs.
+    fn run_matched(
+        id: HotkeyId,
+        key: VKey,
+        state: &KeyboardState,
+        repeat: u32,
+    ) -> Option<KeyAction> {
+        let decision = {
+            let hotkeys = HOTKEYS.lock().unwrap();
+            let hotkey = hotkeys.get(id)?;
+            let cooling_down =
+                !COOLDOWNS
+                    .lock()
+                    .unwrap()
+                    .is_ready(id, hotkey.cooldown, clock::now());
+            // the hotkey still handles the key, without running its callback
+            if cooling_down {
+                return Some(cooldown_action_for(hotkey));
+            }
+            // hotkeys repeating at their own rate ignore the OS auto-repeat
+            if repeat > 0 && hotkey.repeat_rate.is_some() {
+                return Some(key_action_for(hotkey));
+            }
+            hotkey.decision.clone().map(|decide| {
+                (
+                    decide,
+                    hotkey.event(state, repeat),
+                    hotkey.decision_budget,
+                    hotkey.decision_fallback,
+                )
+            })
+        };
+
+        let decided = match decision {
+            Some((decide, event, budget, fallback)) => {
+                match decision::decide(&decide, event, budget, fallback) {
+                    KeyAction::Allow => return None,
+                    action => Some(action),
                 }
+            }
+            None => None,
+        };
+
+        let hotkeys = HOTKEYS.lock().unwrap();
+        // the hotkey may have been unregistered while deciding
+        let hotkey = hotkeys.get(id)?;
+        if fire(hotkey.call(state, repeat)) {
+            HotkeyManager::start_held_repeat(hotkey, key, state, repeat);
+            HotkeyManager::activate(hotkey, key, state, repeat);
+        }
+        Some(decided.unwrap_or_else(|| key_action_for(hotkey)))
+    }
+
+    /// Starts tracking a press bound to `OnHold` or `Tap` hotkeys.
+    ///
+    /// The key down is blocked if any of the hotkeys stops propagation, and it is
+    /// sent again later if the press ends up not triggering anything.
+    fn start_pending_press(hotkeys: &[&Hotkey], key: VKey, state: &KeyboardState) -> KeyAction {
+        if hotkeys.is_empty() {
+            return KeyAction::Allow;
+        }
 
-                // Skip if paused (unless bypass_pause)
-                if paused_state.is_paused() && !hotkey.bypass_pause {
-                    continue;
+        let blocked = hotkeys
+            .iter()
+            .any(|hotkey| hotkey.behaviour == TriggerBehavior::StopPropagation);
+        let mut combo = vec![key];
+        for hotkey in hotkeys {
+            combo.extend(hotkey.modifiers.iter().copied());
+        }
+
+        let mut press = PendingPress::new(key, combo, clock::now(), blocked);
+        for hotkey in hotkeys {
+            match hotkey.trigger_timing {
+                TriggerTiming::OnHold(duration) => press.on_hold(
+                    duration,
+                    (hotkey.call(state, 0), cooldown_action_for(hotkey)),
+                ),
+                TriggerTiming::Tap { max } => {
+                    press.on_tap(max, (hotkey.call(state, 0), cooldown_action_for(hotkey)))
                 }
+                TriggerTiming::OnKeyDown
+                | TriggerTiming::OnKeyUp
+                | TriggerTiming::ModifierTap { .. } => {}
+            }
+        }
 
-                // Check if keyboard state matches hotkey
-                if !hotkey.is_trigger_state(&key, &state) {
-                    continue;
+        let mut timers = TIMERS.lock().unwrap();
+        timers.cancel(|timer| matches!(timer, TimerEvent::HoldElapsed));
+        for deadline in press.hold_deadlines() {
+            timers.schedule(deadline, TimerEvent::HoldElapsed);
+        }
+        *PENDING_PRESS.lock().unwrap() = Some(press);
+
+        if blocked {
+            KeyAction::Block
+        } else {
+            KeyAction::Allow
+        }
+    }
+
+    /// Updates the press waiting for a hold or tap outcome with a new event.
+    ///
+    /// Returns the action to take if the event is an auto-repeat of the pending
+    /// key, and sets `replay` if the event ended a blocked press that has to be
+    /// sent again.
+    fn update_pending_press(
+        key: VKey,
+        event_type: TriggerTiming,
+        replay: &mut Option<VKey>,
+    ) -> Option<KeyAction> {
+        let mut pending = PENDING_PRESS.lock().unwrap();
+        let press = pending.as_ref()?;
+
+        if key == press.key && event_type == TriggerTiming::OnKeyDown {
+            return Some(if press.blocked {
+                KeyAction::Block
+            } else {
+                KeyAction::Allow
+            });
+        }
+
+        if key == press.key {
+            let press = pending.take()?;
+            TIMERS
+                .lock()
+                .unwrap()
+                .cancel(|timer| matches!(timer, TimerEvent::HoldElapsed));
+            let blocked = press.blocked;
+            let outcome = press.release(clock::now());
+            let passes = fire_pending(outcome.fire, clock::now());
+            if outcome.replay || (blocked && passes) {
+                *replay = Some(key);
+            }
+            return None;
+        }
+
+        if event_type == TriggerTiming::OnKeyDown || press.combo.iter().any(|k| k.matches(&key)) {
+            let press = pending.take()?;
+            TIMERS
+                .lock()
+                .unwrap()
+                .cancel(|timer| matches!(timer, TimerEvent::HoldElapsed));
+            let pending_key = press.key;
+            if press.interrupt() {
+                *replay = Some(pending_key);
+            }
+        }
+        None
+    }
+
+    /// Starts repeating a hotkey fired by a fresh key down, if it repeats while held.
+    fn start_held_repeat(hotkey: &Hotkey, key: VKey, state: &KeyboardState, repeat: u32) {
+        let Some(rate) = hotkey.repeat_rate else {
+            return;
+        };
+        if hotkey.trigger_timing != TriggerTiming::OnKeyDown || repeat > 0 {
+            return;
+        }
+
+        let mut combo = vec![key];
+        combo.extend(hotkey.modifiers.iter().copied());
+        let action = RepeatAction {
+            call: hotkey.call(state, 0),
+            bypass_pause: hotkey.bypass_pause,
+        };
+        let mut repeats = HELD_REPEATS.lock().unwrap();
+        repeats.retain(|held| held.action.call.event.id != action.call.event.id);
+        repeats.push(HeldRepeat::new(
+            combo,
+            clock::now(),
+            rate.initial_delay,
+            rate.interval,
+            action,
+        ));
+        schedule_held_repeats(&repeats);
+    }
+
+    /// Tracks the press of a hotkey with a release callback, fired by a fresh key down.
+    fn activate(hotkey: &Hotkey, key: VKey, state: &KeyboardState, repeat: u32) {
+        let Some(release_callback) = &hotkey.release_callback else {
+            return;
+        };
+        if hotkey.trigger_timing != TriggerTiming::OnKeyDown || repeat > 0 {
+            return;
+        }
+
+        let mut combo = vec![key];
+        combo.extend(hotkey.modifiers.iter().copied());
+        let mut release = hotkey.call(state, 0);
+        release.callback = release_callback.clone();
+        release.cooldown = Duration::ZERO;
+        release.event.timing = TriggerTiming::OnKeyUp;
+
+        let previous = ACTIVATIONS.lock().unwrap().activate(
+            release.event.id,
+            combo,
+            hotkey.bypass_pause,
+            release,
+        );
+        run_releases(previous, None);
+    }
+
+    /// Ends the activations and the repeats of the hotkeys `ids`, e.g. when their
+    /// group is paused.
+    pub(crate) fn release_hotkeys(ids: &[HotkeyId]) {
+        let releases = ACTIVATIONS.lock().unwrap().released_hotkeys(ids);
+        run_releases(releases, None);
+
+        let mut repeats = HELD_REPEATS.lock().unwrap();
+        repeats.retain(|held| !ids.contains(&held.action.call.event.id));
+        schedule_held_repeats(&repeats);
+    }
+
+    /// Stops the repeats of the combinations including the released `key`.
+    fn stop_held_repeats(key: VKey) {
+        let mut repeats = HELD_REPEATS.lock().unwrap();
+        let count = repeats.len();
+        repeats.retain(|held| !held.is_released_by(key));
+        if repeats.len() != count {
+            schedule_held_repeats(&repeats);
+        }
+    }
+
+    /// Fires the repeats that are due, pausing or stealing the keyboard stops them.
+    fn process_held_repeats(now: Instant) {
+        let paused = HotkeysPauseHandler::current().is_paused();
+        let stealing = HotkeyManager::current().is_stealing_mode();
+
+        let mut repeats = HELD_REPEATS.lock().unwrap();
+        repeats.retain(|held| !stealing && (!paused || held.action.bypass_pause));
+        for held in repeats.iter_mut() {
+            if let Some(count) = held.elapsed(now) {
+                let mut call = held.action.call.clone();
+                call.event.repeat = count;
+                call.event.timestamp = now;
+                fire(call);
+            }
+        }
+        schedule_held_repeats(&repeats);
+    }
+
+    /// Unregisters an expired hotkey, notifying it if it expired unused.
+    fn process_expiration(id: HotkeyId, usage: &Arc<Usage>) {
+        let removed = {
+            let mut hotkeys = HOTKEYS.lock().unwrap();
+            // the id may have been reused by a new registration since
+            let current = hotkeys
+                .get(id)
+                .is_some_and(|hotkey| Arc::ptr_eq(&hotkey.usage, usage));
+            if current {
+                hotkeys.unregister(id)
+            } else {
+                None
+            }
+        };
+        log_on_dev!("Hotkey {id} expired");
+
+        let Some(hotkey) = removed else {
+            return;
+        };
+        if let (true, Some(callback)) = (usage.is_unused(), hotkey.expire_callback) {
+            run_on_executor_thread(Arc::new(move || callback()));
+        }
+    }
+
+    /// Runs the `ModifierTap` hotkeys of `key` if it was pressed and released alone.
+    fn resolve_modifier_tap(key: VKey, state: &KeyboardState) -> Option<KeyAction> {
+        let held = MODIFIER_TAP_TRACKER
+            .lock()
+            .unwrap()
+            .key_up(key, clock::now())?;
+        let paused = HotkeysPauseHandler::current().is_paused();
+        let paused_groups = PAUSED_GROUPS.lock().unwrap().clone();
+        let layers = LAYERS.lock().unwrap().active();
+
+        let hotkeys = HOTKEYS.lock().unwrap();
+        let matched = resolution_order(
+            hotkeys.iter().filter(|hotkey| {
+                let TriggerTiming::ModifierTap { max } = hotkey.trigger_timing else {
+                    return false;
+                };
+                hotkey.trigger_key.matches(&key)
+                    && max.is_none_or(|max| held <= max)
+                    && (!paused || hotkey.bypass_pause)
+                    && hotkey.enabled
+                    && hotkey
+                        .group
+                        .is_none_or(|group| !paused_groups.contains(&group))
+                    && layer::depth(&layers, hotkey.layer.as_deref()).is_some()
+                    && hotkey.usage.is_active(clock::now())
+            }),
+            &layers,
+        );
+        let hotkey = matched.first()?;
+
+        if !fire(hotkey.call(state, 0)) {
+            return Some(cooldown_action_for(hotkey));
+        }
+        Some(key_action_for(hotkey))
+    }
+
+    /// Handles an event while the keyboard is stolen, runs the exempt hotkeys.
+    ///
+    /// Key presses are blocked, the releases of the keys pressed while stealing too
+    /// if the options say so.
+    fn process_stealing(event: &KeyboardInputEvent) -> Option<KeyAction> {
+        let options = STEAL_OPTIONS.lock().unwrap().clone()?;
+        let (key, state, timing) = match event {
+            KeyboardInputEvent::KeyDown { key, state } => (*key, state, TriggerTiming::OnKeyDown),
+            KeyboardInputEvent::KeyUp { key, state } => (*key, state, TriggerTiming::OnKeyUp),
+        };
+
+        if timing == TriggerTiming::OnKeyDown && options.is_exit(key, state) {
+            // the exit key frees the keyboard, but is still blocked
+            HotkeyManager::current().free_keyboard_for(FreeReason::ExitKey);
+            return Some(KeyAction::Block);
+        }
+        {
+            let mut stolen = STOLEN_KEYS.lock().unwrap();
+            if timing == TriggerTiming::OnKeyDown {
+                if !stolen.contains(&key) {
+                    stolen.push(key);
+                }
+            } else {
+                let count = stolen.len();
+                stolen.retain(|stolen_key| *stolen_key != key);
+                // the other applications saw the press of this key, so they see its release
+                if !options.block_key_ups || stolen.len() == count {
+                    return None;
                 }
+            }
+        }
+
+        let paused = HotkeysPauseHandler::current().is_paused();
+        let paused_groups = PAUSED_GROUPS.lock().unwrap().clone();
+        let hotkeys = HOTKEYS.lock().unwrap();
+        let exempt = resolution_order(
+            hotkeys.bucket(key).iter().filter(|hotkey| {
+                hotkey.id.is_some_and(|id| options.exempt.contains(&id))
+                    && hotkey.trigger_timing == timing
+                    && (!paused || hotkey.bypass_pause)
+                    && hotkey.enabled
+                    && hotkey
+                        .group
+                        .is_none_or(|group| !paused_groups.contains(&group))
+                    && hotkey.is_trigger_state(&key, state)
+            }),
+            &[],
+        );
+        if let Some(hotkey) = exempt.first() {
+            fire(hotkey.call(state, 0));
+        }
+        Some(match timing {
+            TriggerTiming::OnKeyUp => KeyAction::Suppress,
+            _ => KeyAction::Block,
+        })
+    }
+
+    /// Feeds the shortcut being recorded, key presses are blocked while recording.
+    fn process_shortcut_recording(event: &KeyboardInputEvent) -> Option<KeyAction> {
+        let mut recording = SHORTCUT_RECORDING.lock().unwrap();
+        let current = recording.as_mut()?;
+        if let Some(outcome) = current.recorder.handle(event) {
+            let _ = current.outcome.send(outcome);
+            *recording = None;
+        }
+        match event {
+            KeyboardInputEvent::KeyDown { .. } => Some(KeyAction::Block),
+            KeyboardInputEvent::KeyUp { .. } => None,
+        }
+    }
+
+    /// A mouse click while a modifier is held means it is not a modifier tap,
+    /// and the next characters may be typed elsewhere.
+    fn process_mouse_event() {
+        MODIFIER_TAP_TRACKER.lock().unwrap().interrupt();
+        HOTSTRINGS.lock().unwrap().reset();
+        hook::report_mouse_clicks(false);
+    }
+
+    /// Asks the hook for the mouse clicks only while they can change something.
+    fn update_mouse_clicks() {
+        let wanted = MODIFIER_TAP_TRACKER.lock().unwrap().is_pending()
+            || HOTSTRINGS.lock().unwrap().is_typing();
+        hook::report_mouse_clicks(wanted);
+    }
+
+    /// Feeds a key press to the hotstrings, running the callback of the completed one.
+    fn process_hotstrings(key: VKey, state: &KeyboardState) {
+        let paused = HotkeysPauseHandler::current().is_paused();
+        let fired = HOTSTRINGS
+            .lock()
+            .unwrap()
+            .key_down(key, state, paused, clock::now());
+        if let Some((callback, event)) = fired {
+            run_on_executor_thread(Arc::new(move || callback(&event)));
+        }
+    }
+
+    /// Decides what to do with a tap of a combination bound to multi-tap hotkeys.
+    fn resolve_taps(
+        matched: &[&Hotkey],
+        key: VKey,
+        state: &KeyboardState,
+        event_type: TriggerTiming,
+        repeat: u32,
+    ) -> KeyAction {
+        let blocks = matched
+            .iter()
+            .any(|hotkey| hotkey.behaviour == TriggerBehavior::StopPropagation);
 
-                // Execute hotkey callback
-                run_on_executor_thread(hotkey.callback.clone());
+        // holding the key is not a tap, keep the decision of the first press
+        if repeat > 0 {
+            return if blocks {
+                KeyAction::Block
+            } else {
+                KeyAction::Allow
+            };
+        }
+
+        let combo = {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            key.hash(&mut hasher);
+            event_type.hash(&mut hasher);
+            state.is_win_pressed().hash(&mut hasher);
+            state.is_control_pressed().hash(&mut hasher);
+            state.is_shift_pressed().hash(&mut hasher);
+            state.is_menu_pressed().hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let taps: Vec<u8> = matched.iter().map(|hotkey| hotkey.taps).collect();
+        let window = matched
+            .iter()
+            .map(|hotkey| hotkey.tap_window)
+            .max()
+            .unwrap_or_default();
 
-                // Return appropriate action based on behavior
-                return match hotkey.behaviour {
-                    TriggerBehavior::PassThrough => KeyAction::Allow,
-                    TriggerBehavior::StopPropagation => KeyAction::Block,
+        // a new tap always cancels the deferred hotkey of the previous one
+        TIMERS.lock().unwrap().cancel(
+            |timer| matches!(timer, TimerEvent::TapWindowElapsed { combo: c, .. } if *c == combo),
+        );
+
+        let decision = TAP_TRACKER
+            .lock()
+            .unwrap()
+            .tap(combo, key, &taps, window, clock::now());
+        log_on_dev!("Tap decision: {decision:?}");
+
+        let find = |taps: u8| matched.iter().find(|hotkey| hotkey.taps == taps);
+        match decision {
+            TapDecision::Fire(taps) => {
+                let Some(hotkey) = find(taps) else {
+                    return KeyAction::Allow;
                 };
+                if !fire(hotkey.call(state, 0)) {
+                    return cooldown_action_for(hotkey);
+                }
+                key_action_for(hotkey)
             }
+            TapDecision::Defer { taps, deadline } => {
+                let Some(hotkey) = find(taps) else {
+                    return KeyAction::Allow;
+                };
+                TIMERS.lock().unwrap().schedule(
+                    deadline,
+                    TimerEvent::TapWindowElapsed {
+                        combo,
+                        call: hotkey.call(state, 0),
+                    },
+                );
+                key_action_for(hotkey)
+            }
+            TapDecision::Wait | TapDecision::Miss => KeyAction::Allow,
         }
+    }
 
-        KeyAction::Allow
+    /// Runs the scheduled events whose deadline is reached.
+    pub(crate) fn process_timers(now: Instant) {
+        let due = TIMERS.lock().unwrap().take_due(now);
+        for event in due {
+            match event {
+                TimerEvent::TapWindowElapsed { combo, mut call } => {
+                    TAP_TRACKER.lock().unwrap().finish(combo);
+                    call.event.timestamp = now;
+                    fire(call);
+                }
+                TimerEvent::HeldRepeatDue => HotkeyManager::process_held_repeats(now),
+                TimerEvent::HotkeyExpired { id, usage } => {
+                    HotkeyManager::process_expiration(id, &usage);
+                }
+                TimerEvent::StealTimeout => {
+                    let manager = HotkeyManager::current();
+                    if manager.is_stealing_mode() {
+                        manager.free_keyboard_for(FreeReason::Timeout);
+                    }
+                }
+                TimerEvent::PauseElapsed => HotkeysPauseHandler::current().expire(now),
+                TimerEvent::HoldElapsed => {
+                    if let Some(press) = PENDING_PRESS.lock().unwrap().as_mut() {
+                        let passes = fire_pending(press.hold_elapsed(now), now);
+                        if press.blocked && passes {
+                            // the key is still held, its press goes through late
+                            hook::send_key_events(&[(press.key, true)]);
+                            press.blocked = false;
+                        }
+                    }
+                }
+            }
+        }
     }
 
     /// This gracefully interrupt the event loop by sending
     /// a control signal. This allows the `HotkeyManager` to clean up resources and stop
     /// processing keyboard events.
     pub fn stop_keyboard_capturing() {
+        let releases = ACTIVATIONS.lock().unwrap().release_all();
+        run_releases(releases, None);
         EventLoopEvent::send(EventLoopEvent::Stop);
         hook::stop();
         client_executor::stop_executor_thread();
@@ -220,17 +1244,199 @@ impl HotkeyManager {
         CLIENT_KEYBOARD_CALLBACK.store(None);
     }
 
+    /// Replaces the clock used by timing based triggers (taps, holds...).
+    ///
+    /// This is mostly useful on tests, to drive the timing logic with a
+    /// [`crate::clock::ManualClock`].
+    pub fn set_clock<C: Clock + 'static>(&self, clock: C) {
+        clock::set(clock);
+    }
+
+    /// Sets the minimum time between any two hotkey callback runs, none by default.
+    ///
+    /// Hotkeys matching within the interval don't run their callback, the key is
+    /// handled as during a cooldown, see [`Hotkey::cooldown_behavior`].
+    pub fn set_min_callback_interval(&self, interval: Duration) {
+        COOLDOWNS.lock().unwrap().set_min_interval(interval);
+    }
+
+    /// Sets which hotkeys run when several hotkeys match the same event.
+    pub fn set_match_policy(&self, policy: MatchPolicy) {
+        *MATCH_POLICY.lock().unwrap() = policy;
+    }
+
+    /// Returns the current [`MatchPolicy`].
+    pub fn match_policy(&self) -> MatchPolicy {
+        *MATCH_POLICY.lock().unwrap()
+    }
+
+    /// Sets a callback notified on the executor thread when the hotkeys are paused
+    /// or resumed, see [`HotkeysPauseHandler`].
+    pub fn set_pause_listener<F>(&self, cb: F)
+    where
+        F: Fn(&PauseChange) + Send + Sync + 'static,
+    {
+        CLIENT_PAUSE_CB.store(Some(Arc::new(Box::new(cb))));
+    }
+
+    pub fn remove_pause_listener(&self) {
+        CLIENT_PAUSE_CB.store(None);
+    }
+
     /// Signals the `HotkeyManager` to pause processing of hotkeys.
     pub fn pause_handler(&self) -> HotkeysPauseHandler {
         HotkeysPauseHandler { state: self.paused }
     }
 }
 
+/// Collects the matching hotkeys in the order they are resolved, see [`MatchPolicy`].
+///
+/// The hotkeys of the upper `layers` come first, hotkeys of inactive layers last.
+fn resolution_order<'a, I: IntoIterator<Item = &'a Hotkey>>(
+    hotkeys: I,
+    layers: &[String],
+) -> Vec<&'a Hotkey> {
+    let mut matched: Vec<&Hotkey> = hotkeys.into_iter().collect();
+    // hotkeys with the same precedence are ordered by registration, whatever their
+    // trigger key, the stable sort keeps the given order of unregistered hotkeys
+    matched.sort_by(|a, b| {
+        let depth = |hotkey: &Hotkey| layer::depth(layers, hotkey.layer.as_deref());
+        depth(b).cmp(&depth(a)).then(a.cmp_precedence(b))
+    });
+    matched
+}
+
+/// Schedules the expiration of the hotkeys of the active profile.
+fn schedule_profile_expirations(profiles: &Profiles, hotkeys: &HotkeyRegistry) {
+    let mut scheduled = false;
+    for id in profiles.active_ids() {
+        if let Some(hotkey) = hotkeys.get(id) {
+            scheduled |= schedule_expiration(hotkey);
+        }
+    }
+    if scheduled {
+        EventLoopEvent::send(EventLoopEvent::TimersChanged);
+    }
+}
+
+/// Notifies the layer change listener, if the active layers changed.
+fn notify_layer_change(change: Option<LayerChange>) {
+    let Some(change) = change else {
+        return;
+    };
+    log_on_dev!("Active layers: {:?}", change.active);
+    if let Some(cb) = CLIENT_LAYER_CHANGE_CB.load().as_ref() {
+        let cb = cb.clone();
+        run_on_executor_thread(Arc::new(move || cb(&change)));
+    }
+}
+
+/// Runs the release callbacks of ended activations, with the current state if known.
+fn run_releases<I: IntoIterator<Item = HotkeyCall>>(releases: I, state: Option<&KeyboardState>) {
+    for mut release in releases {
+        if let Some(state) = state {
+            release.event.state = state.clone();
+        }
+        release.event.timestamp = clock::now();
+        run_hotkey_on_executor_thread(release);
+    }
+}
+
+/// Replaces the repeat timers by the deadlines of the held combinations.
+fn schedule_held_repeats(repeats: &[HeldRepeat<RepeatAction>]) {
+    let mut timers = TIMERS.lock().unwrap();
+    timers.cancel(|timer| matches!(timer, TimerEvent::HeldRepeatDue));
+    for held in repeats {
+        timers.schedule(held.deadline(), TimerEvent::HeldRepeatDue);
+    }
+}
+
+/// Schedules the expiration of a registered hotkey, returns whether it expires.
+fn schedule_expiration(hotkey: &Hotkey) -> bool {
+    let Some(expires_at) = hotkey.usage.expires_at() else {
+        return false;
+    };
+    TIMERS.lock().unwrap().schedule(
+        expires_at,
+        TimerEvent::HotkeyExpired {
+            id: hotkey.id.unwrap_or_default(),
+            usage: hotkey.usage.clone(),
+        },
+    );
+    true
+}
+
+/// Runs the callback unless the hotkey is cooling down or out of uses,
+/// returns whether it ran.
+fn fire(call: HotkeyCall) -> bool {
+    let now = clock::now();
+    if !call.usage.is_active(now) {
+        return false;
+    }
+    let ready = COOLDOWNS
+        .lock()
+        .unwrap()
+        .try_run(call.event.id, call.cooldown, now);
+    if !ready {
+        log_on_dev!("Hotkey {} is cooling down", call.event.id);
+        return false;
+    }
+
+    // the layer switches on the press, not on its auto-repeats
+    if let (Some(switch), 0) = (&call.layer_switch, call.event.repeat) {
+        notify_layer_change(LAYERS.lock().unwrap().apply(switch));
+    }
+    if call.usage.try_use(now) == Some(true) {
+        // last use, the event loop unregisters the hotkey right after this event
+        TIMERS.lock().unwrap().schedule(
+            now,
+            TimerEvent::HotkeyExpired {
+                id: call.event.id,
+                usage: call.usage.clone(),
+            },
+        );
+    }
+    run_hotkey_on_executor_thread(call);
+    true
+}
+
+/// Fires the calls of a hold or tap outcome, returns whether the key has to go
+/// through, as nothing fired and a hotkey cooling down passes it through.
+fn fire_pending(calls: Vec<PendingCall>, now: Instant) -> bool {
+    let mut fired = false;
+    let mut passes = false;
+    for (mut call, cooldown_action) in calls {
+        call.event.timestamp = now;
+        if fire(call) {
+            fired = true;
+        } else {
+            passes |= cooldown_action == KeyAction::Allow;
+        }
+    }
+    passes && !fired
+}
+
+fn cooldown_action_for(hotkey: &Hotkey) -> KeyAction {
+    match hotkey.cooldown_behavior.unwrap_or(hotkey.behaviour) {
+        TriggerBehavior::PassThrough => KeyAction::Allow,
+        TriggerBehavior::StopPropagation => KeyAction::Block,
+    }
+}
+
+fn key_action_for(hotkey: &Hotkey) -> KeyAction {
+    match hotkey.behaviour {
+        TriggerBehavior::PassThrough => KeyAction::Allow,
+        TriggerBehavior::StopPropagation => KeyAction::Block,
+    }
+}
+
 /// A handle for signaling the `HotkeyManager` to stop processing hotkeys without
 /// exiting the event loop or unregistering hotkeys. When paused, the `HotkeyManager`
 /// will only process registered pause hotkeys.
 ///
 /// The `PauseHandle` is used to manage the pause state of the `HotkeyManager`.
+/// The hotkeys are paused while any reason is active: the manual pause, a
+/// [`PauseGuard`] or a timed pause.
 pub struct HotkeysPauseHandler {
     state: &'static AtomicBool,
 }
@@ -241,18 +1447,90 @@ impl HotkeysPauseHandler {
         Self { state: &PAUSED }
     }
 
-    /// Toggles the pause state of the `HotkeyManager`.
+    /// Toggles the manual pause of the `HotkeyManager`.
     ///
-    /// If the `HotkeyManager` is currently paused, calling this method will resume
-    /// normal hotkey processing. If it is active, calling this method will pause it.
+    /// If the manual pause is set, calling this method will clear it. If it is not,
+    /// calling this method will set it, see [`HotkeysPauseHandler::set`].
     pub fn toggle(&self) {
-        self.state
-            .store(!self.state.load(Ordering::Relaxed), Ordering::Relaxed);
+        let manual = PAUSE_STATE.lock().unwrap().is_manual();
+        self.set(!manual);
     }
 
-    /// Explicitly sets the pause state.
+    /// Explicitly sets the manual pause.
+    ///
+    /// The hotkeys stay paused while a [`PauseGuard`] or a timed pause is active,
+    /// even if the manual pause is cleared.
     pub fn set(&self, state: bool) {
-        self.state.store(state, Ordering::Relaxed);
+        self.update(|pause| pause.set_manual(state));
+    }
+
+    /// Pauses the hotkeys until the returned guard is dropped, `reason` is listed
+    /// by [`HotkeysPauseHandler::reasons`] meanwhile.
+    pub fn pause<S: Into<String>>(&self, reason: S) -> PauseGuard {
+        let reason = reason.into();
+        let mut token = 0;
+        self.update(|pause| token = pause.add(reason, None));
+        PauseGuard { token }
+    }
+
+    /// Pauses the hotkeys for `duration`, they resume on their own once it elapsed
+    /// if nothing else keeps them paused.
+    ///
+    /// The pause is ended by the event loop, which has to be running.
+    pub fn pause_for<S: Into<String>>(&self, duration: Duration, reason: S) {
+        let reason = reason.into();
+        let until = clock::now() + duration;
+        self.update(|pause| {
+            pause.add(reason, Some(until));
+        });
+        TIMERS
+            .lock()
+            .unwrap()
+            .schedule(until, TimerEvent::PauseElapsed);
+        EventLoopEvent::send(EventLoopEvent::TimersChanged);
+    }
+
+    /// Returns the reasons the hotkeys are paused for, oldest first, the manual
+    /// pause excepted.
+    pub fn reasons(&self) -> Vec<String> {
+        PAUSE_STATE.lock().unwrap().reasons()
+    }
+
+    /// Ends the pause of a dropped guard.
+    pub(crate) fn resume_token(&self, token: u64) {
+        self.update(|pause| pause.remove(token));
+    }
+
+    /// Ends the timed pauses elapsed at `now`.
+    fn expire(&self, now: Instant) {
+        self.update(|pause| pause.expire(now));
+    }
+
+    /// Updates the pause reasons, and the pause state if it changed.
+    ///
+    /// Pausing releases the pressed hotkeys that don't bypass the pause, see
+    /// [`Hotkey::on_release`].
+    fn update<F: FnOnce(&mut PauseState)>(&self, update: F) {
+        let change = {
+            let mut pause = PAUSE_STATE.lock().unwrap();
+            let was_paused = pause.is_paused();
+            update(&mut pause);
+            self.state.store(pause.is_paused(), Ordering::Relaxed);
+            (pause.is_paused() != was_paused).then(|| pause.change())
+        };
+        let Some(change) = change else {
+            return;
+        };
+
+        log_on_dev!("Hotkeys paused: {}", change.paused);
+        if change.paused {
+            let releases = ACTIVATIONS.lock().unwrap().paused();
+            run_releases(releases, None);
+        }
+        if let Some(cb) = CLIENT_PAUSE_CB.load().as_ref() {
+            let cb = cb.clone();
+            run_on_executor_thread(Arc::new(move || cb(&change)));
+        }
     }
 
     /// Returns whether the `HotkeyManager` is currently paused.
@@ -268,7 +1546,7 @@ impl HotkeyManager {
     /// this functions returns a map of initial hotkeys,
     /// these are no-overridable as they are important system hotkeys
     /// like lock screen and security screen
-    fn get_initial_hotkeys() -> HashMap<VKey, HashSet<Hotkey>> {
+    pub(crate) fn get_initial_hotkeys() -> Vec<Hotkey> {
         let lock_screen_shortcut = Hotkey::new(VKey::L, [VKey::LWin], || {
             log_on_dev!("Locking screen");
             KEYBOARD_STATE.lock().unwrap().request_syncronization();
@@ -284,9 +1562,536 @@ impl HotkeyManager {
             .bypass_pause()
             .behavior(TriggerBehavior::PassThrough);
 
-        let mut hotkeys = HashMap::new();
-        hotkeys.insert(VKey::L, HashSet::from([lock_screen_shortcut]));
-        hotkeys.insert(VKey::Delete, HashSet::from([security_screen_shortcut]));
-        hotkeys
+        vec![lock_screen_shortcut, security_screen_shortcut]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::output::{InputEvent, RecordingSink};
+    use crate::ModifierMatch;
+    use std::sync::{MutexGuard, Once, PoisonError};
+
+    /// The event loop state is global, the tests driving it run one at a time.
+    static ENGINE: Mutex<()> = Mutex::new(());
+
+    /// Drives the event loop logic without the hook, on a manual clock, the
+    /// injected keys are recorded.
+    struct Engine {
+        clock: ManualClock,
+        sink: RecordingSink,
+        state: KeyboardState,
+        _guard: MutexGuard<'static, ()>,
+    }
+
+    impl Engine {
+        fn new() -> Self {
+            static EXECUTOR: Once = Once::new();
+            let guard = ENGINE.lock().unwrap_or_else(PoisonError::into_inner);
+            EXECUTOR.call_once(client_executor::start_executor_thread);
+
+            let clock = ManualClock::new();
+            clock::set(clock.clone());
+            let sink = RecordingSink::new();
+            hook::set_key_sink(sink.clone());
+            Engine {
+                clock,
+                sink,
+                state: KeyboardState::new(),
+                _guard: guard,
+            }
+        }
+
+        fn press(&mut self, key: VKey) -> KeyAction {
+            self.state.keydown(key);
+            HotkeyManager::process_keyboard_event(KeyboardInputEvent::KeyDown {
+                key,
+                state: self.state.clone(),
+            })
+        }
+
+        fn release(&mut self, key: VKey) -> KeyAction {
+            self.state.keyup(key);
+            HotkeyManager::process_keyboard_event(KeyboardInputEvent::KeyUp {
+                key,
+                state: self.state.clone(),
+            })
+        }
+
+        fn advance(&self, duration: Duration) {
+            self.clock.advance(duration);
+            HotkeyManager::process_timers(clock::now());
+        }
+
+        fn injected(&self) -> Vec<(VKey, bool)> {
+            self.sink
+                .events()
+                .into_iter()
+                .filter_map(|event| match event {
+                    InputEvent::Key { key, down } => Some((key, down)),
+                    InputEvent::Unicode { .. } => None,
+                })
+                .collect()
+        }
+    }
+
+    fn ctrl_shift_a() -> KeyboardState {
+        let mut state = KeyboardState::new();
+        state.keydown(VKey::LControl);
+        state.keydown(VKey::LShift);
+        state.keydown(VKey::A);
+        state
+    }
+
+    /// Registers the hotkeys in order and returns the names of the ones matching
+    /// "Ctrl + Shift + A", in resolution order.
+    fn resolve(hotkeys: Vec<(&'static str, Hotkey)>) -> Vec<&'static str> {
+        let state = ctrl_shift_a();
+        let (names, hotkeys): (Vec<&str>, Vec<Hotkey>) = hotkeys.into_iter().unzip();
+        resolution_order(
+            hotkeys
+                .iter()
+                .filter(|hotkey| hotkey.is_trigger_state(&VKey::A, &state)),
+            &["nav".to_owned()],
+        )
+        .into_iter()
+        .map(|matched| {
+            let index = hotkeys
+                .iter()
+                .position(|hotkey| std::ptr::eq(hotkey, matched))
+                .unwrap();
+            names[index]
+        })
+        .collect()
+    }
+
+    fn hotkey(modifiers: &[VKey]) -> Hotkey {
+        Hotkey::new(VKey::A, modifiers, || {})
+    }
+
+    #[test]
+    fn test_priority_wins() {
+        let order = resolve(vec![
+            (
+                "exact",
+                hotkey(&[VKey::Control, VKey::Shift]).strict_sequence(),
+            ),
+            (
+                "relaxed",
+                hotkey(&[VKey::Control])
+                    .modifier_match(ModifierMatch::AtLeast)
+                    .priority(10),
+            ),
+            (
+                "negative",
+                hotkey(&[VKey::Shift])
+                    .modifier_match(ModifierMatch::AtLeast)
+                    .priority(-1),
+            ),
+        ]);
+        assert_eq!(order, vec!["relaxed", "exact", "negative"]);
+    }
+
+    #[test]
+    fn test_strictest_modifier_match_wins() {
+        let order = resolve(vec![
+            (
+                "any_of",
+                hotkey(&[VKey::Control, VKey::Shift]).modifier_match(ModifierMatch::AnyOf),
+            ),
+            (
+                "at_least",
+                hotkey(&[VKey::Control]).modifier_match(ModifierMatch::AtLeast),
+            ),
+            ("exact", hotkey(&[VKey::Control, VKey::Shift])),
+        ]);
+        assert_eq!(order, vec!["exact", "at_least", "any_of"]);
+    }
+
+    #[test]
+    fn test_strict_sequence_wins() {
+        let order = resolve(vec![
+            ("exact", hotkey(&[VKey::Control, VKey::Shift])),
+            (
+                "strict",
+                hotkey(&[VKey::Control, VKey::Shift])
+                    .strict_sequence()
+                    .bypass_pause(),
+            ),
+        ]);
+        assert_eq!(order, vec!["strict", "exact"]);
+    }
+
+    #[test]
+    fn test_more_modifiers_win() {
+        let order = resolve(vec![
+            (
+                "ctrl",
+                hotkey(&[VKey::Control]).modifier_match(ModifierMatch::AtLeast),
+            ),
+            (
+                "ctrl_shift",
+                hotkey(&[VKey::Control, VKey::Shift]).modifier_match(ModifierMatch::AtLeast),
+            ),
+        ]);
+        assert_eq!(order, vec!["ctrl_shift", "ctrl"]);
+    }
+
+    #[test]
+    fn test_ties_keep_registration_order() {
+        let first = vec![
+            (
+                "pause",
+                hotkey(&[VKey::Control, VKey::Shift]).bypass_pause(),
+            ),
+            ("normal", hotkey(&[VKey::LControl, VKey::LShift])),
+        ];
+        assert_eq!(resolve(first), vec!["pause", "normal"]);
+
+        let second = vec![
+            ("normal", hotkey(&[VKey::LControl, VKey::LShift])),
+            (
+                "pause",
+                hotkey(&[VKey::Control, VKey::Shift]).bypass_pause(),
+            ),
+        ];
+        assert_eq!(resolve(second), vec!["normal", "pause"]);
+    }
+
+    #[test]
+    fn test_upper_layer_wins() {
+        let order = resolve(vec![
+            ("base", hotkey(&[VKey::Control, VKey::Shift]).priority(10)),
+            (
+                "nav",
+                hotkey(&[VKey::Control]).modifier_match(ModifierMatch::AtLeast),
+            ),
+        ]);
+        assert_eq!(order, vec!["base", "nav"]);
+
+        let order = resolve(vec![
+            ("base", hotkey(&[VKey::Control, VKey::Shift]).priority(10)),
+            (
+                "nav",
+                hotkey(&[VKey::Control])
+                    .modifier_match(ModifierMatch::AtLeast)
+                    .layer("nav"),
+            ),
+        ]);
+        assert_eq!(order, vec!["nav", "base"]);
+    }
+
+    #[test]
+    fn test_blocked_press_is_sent_again_before_its_release() {
+        let mut engine = Engine::new();
+        let hkm = HotkeyManager::current();
+        let hold = hkm
+            .register_hotkey(
+                Hotkey::new(VKey::Capital, [], || {})
+                    .trigger_timing(TriggerTiming::OnHold(Duration::from_millis(500))),
+            )
+            .unwrap();
+
+        assert_eq!(engine.press(VKey::Capital), KeyAction::Block);
+        engine.advance(Duration::from_millis(100));
+        assert_eq!(engine.release(VKey::Capital), KeyAction::Suppress);
+        assert_eq!(
+            engine.injected(),
+            [(VKey::Capital, true), (VKey::Capital, false)]
+        );
+
+        hkm.unregister_hotkey(hold).unwrap();
+    }
+
+    #[test]
+    fn test_tap_cooling_down_passes_the_press_through() {
+        let mut engine = Engine::new();
+        let hkm = HotkeyManager::current();
+        let tap = hkm
+            .register_hotkey(
+                Hotkey::new(VKey::Capital, [], || {})
+                    .trigger_timing(TriggerTiming::Tap {
+                        max: Duration::from_millis(300),
+                    })
+                    .cooldown(Duration::from_secs(1))
+                    .cooldown_behavior(TriggerBehavior::PassThrough),
+            )
+            .unwrap();
+
+        assert_eq!(engine.press(VKey::Capital), KeyAction::Block);
+        engine.advance(Duration::from_millis(50));
+        assert_eq!(engine.release(VKey::Capital), KeyAction::Allow);
+        assert!(engine.injected().is_empty(), "The tap consumed the press");
+
+        assert_eq!(engine.press(VKey::Capital), KeyAction::Block);
+        engine.advance(Duration::from_millis(50));
+        assert_eq!(engine.release(VKey::Capital), KeyAction::Suppress);
+        assert_eq!(
+            engine.injected(),
+            [(VKey::Capital, true), (VKey::Capital, false)]
+        );
+
+        hkm.unregister_hotkey(tap).unwrap();
+    }
+
+    #[test]
+    fn test_blocked_press_is_sent_again_before_a_modifier_release() {
+        let mut engine = Engine::new();
+        let hkm = HotkeyManager::current();
+        let hold = hkm
+            .register_hotkey(
+                Hotkey::new(VKey::Capital, [VKey::Control], || {})
+                    .trigger_timing(TriggerTiming::OnHold(Duration::from_millis(500))),
+            )
+            .unwrap();
+
+        assert_eq!(engine.press(VKey::LControl), KeyAction::Allow);
+        assert_eq!(engine.press(VKey::Capital), KeyAction::Block);
+        assert_eq!(engine.release(VKey::LControl), KeyAction::Suppress);
+        assert_eq!(engine.release(VKey::Capital), KeyAction::Allow);
+        assert_eq!(
+            engine.injected(),
+            [(VKey::Capital, true), (VKey::LControl, false)]
+        );
+
+        hkm.unregister_hotkey(hold).unwrap();
+    }
+
+    #[test]
+    fn test_stealing_timeout_frees_the_keyboard() {
+        let mut engine = Engine::new();
+        let hkm = HotkeyManager::current();
+        let (freed_tx, freed) = crossbeam_channel::unbounded();
+        hkm.steal_keyboard_with(
+            StealOptions::new().timeout(Duration::from_secs(5)),
+            move |reason| {
+                let _ = freed_tx.send(reason);
+            },
+        );
+
+        assert_eq!(engine.press(VKey::A), KeyAction::Block);
+        engine.advance(Duration::from_secs(4));
+        assert!(hkm.is_stealing_mode());
+        engine.advance(Duration::from_secs(1));
+        assert_eq!(
+            freed.recv_timeout(Duration::from_secs(1)),
+            Ok(FreeReason::Timeout)
+        );
+        assert!(!hkm.is_stealing_mode());
+        assert_eq!(engine.release(VKey::A), KeyAction::Allow);
+    }
+
+    #[test]
+    fn test_stealing_only_blocks_the_releases_of_stolen_presses() {
+        let mut engine = Engine::new();
+        let hkm = HotkeyManager::current();
+
+        assert_eq!(engine.press(VKey::LControl), KeyAction::Allow);
+        hkm.steal_keyboard_with(StealOptions::new().block_key_ups(), |_| {});
+        assert_eq!(engine.press(VKey::A), KeyAction::Block);
+        assert_eq!(
+            engine.release(VKey::LControl),
+            KeyAction::Allow,
+            "The press went through, so does the release"
+        );
+        assert_eq!(engine.release(VKey::A), KeyAction::Suppress);
+
+        hkm.free_keyboard();
+    }
+
+    #[test]
+    fn test_stealing_runs_the_exempt_hotkeys() {
+        let mut engine = Engine::new();
+        let hkm = HotkeyManager::current();
+        let (fired_tx, fired) = crossbeam_channel::unbounded();
+        let blocked_tx = fired_tx.clone();
+        let exempt = hkm
+            .register_hotkey(Hotkey::new(VKey::F13, [], move || {
+                let _ = fired_tx.send(VKey::F13);
+            }))
+            .unwrap();
+        let other = hkm
+            .register_hotkey(Hotkey::new(VKey::F14, [], move || {
+                let _ = blocked_tx.send(VKey::F14);
+            }))
+            .unwrap();
+        let (freed_tx, freed) = crossbeam_channel::unbounded();
+        hkm.steal_keyboard_with(
+            StealOptions::new().exempt(exempt).block_key_ups(),
+            move |reason| {
+                let _ = freed_tx.send(reason);
+            },
+        );
+
+        assert_eq!(engine.press(VKey::F14), KeyAction::Block);
+        assert_eq!(engine.release(VKey::F14), KeyAction::Suppress);
+        assert_eq!(engine.press(VKey::F13), KeyAction::Block);
+        assert_eq!(engine.release(VKey::F13), KeyAction::Suppress);
+        // the callbacks run in order, so F14 would have fired first
+        assert_eq!(fired.recv_timeout(Duration::from_secs(1)), Ok(VKey::F13));
+
+        assert_eq!(engine.press(VKey::Escape), KeyAction::Block);
+        assert_eq!(
+            freed.recv_timeout(Duration::from_secs(1)),
+            Ok(FreeReason::ExitKey)
+        );
+        assert_eq!(engine.release(VKey::Escape), KeyAction::Allow);
+
+        hkm.unregister_hotkey(exempt).unwrap();
+        hkm.unregister_hotkey(other).unwrap();
+    }
+
+    #[test]
+    fn test_disabling_stops_a_held_hotkey() {
+        let mut engine = Engine::new();
+        let hkm = HotkeyManager::current();
+        let (fired_tx, fired) = crossbeam_channel::unbounded();
+        let released_tx = fired_tx.clone();
+        let id = hkm
+            .register_hotkey(
+                Hotkey::new(VKey::F15, [], move || {
+                    let _ = fired_tx.send("fire");
+                })
+                .repeat_while_held(Duration::from_millis(300), Duration::from_millis(100))
+                .on_release(move || {
+                    let _ = released_tx.send("release");
+                }),
+            )
+            .unwrap();
+        let next = || fired.recv_timeout(Duration::from_secs(1));
+
+        assert_eq!(engine.press(VKey::F15), KeyAction::Block);
+        assert_eq!(next(), Ok("fire"));
+        engine.advance(Duration::from_millis(300));
+        assert_eq!(next(), Ok("fire"), "The hotkey repeats while held");
+
+        hkm.set_enabled(id, false).unwrap();
+        assert_eq!(next(), Ok("release"));
+        engine.advance(Duration::from_millis(500));
+        assert_eq!(engine.release(VKey::F15), KeyAction::Allow);
+        assert!(
+            fired.recv_timeout(Duration::from_millis(100)).is_err(),
+            "Disabling stops the repeats and ends the press"
+        );
+
+        hkm.unregister_hotkey(id).unwrap();
+    }
+
+    #[test]
+    fn test_group_hotkeys_stop_with_their_group() {
+        let mut engine = Engine::new();
+        let hkm = HotkeyManager::current();
+        let group = hkm.create_group("plugin");
+        let (fired_tx, fired) = crossbeam_channel::unbounded();
+        let released_tx = fired_tx.clone();
+        group
+            .register_hotkey(
+                Hotkey::new(VKey::F16, [], move || {
+                    let _ = fired_tx.send("fire");
+                })
+                .repeat_while_held(Duration::from_millis(300), Duration::from_millis(100))
+                .on_release(move || {
+                    let _ = released_tx.send("release");
+                }),
+            )
+            .unwrap();
+        let next = || fired.recv_timeout(Duration::from_secs(1));
+        let tap = |engine: &mut Engine| {
+            let action = engine.press(VKey::F16);
+            engine.release(VKey::F16);
+            action
+        };
+
+        assert_eq!(engine.press(VKey::F16), KeyAction::Block);
+        assert_eq!(next(), Ok("fire"));
+        group.set_enabled(false);
+        assert_eq!(next(), Ok("release"));
+        engine.advance(Duration::from_millis(500));
+        assert_eq!(engine.release(VKey::F16), KeyAction::Allow);
+        assert_eq!(tap(&mut engine), KeyAction::Allow, "Disabled group");
+
+        group.set_enabled(true);
+        group.pause();
+        assert_eq!(tap(&mut engine), KeyAction::Allow, "Paused group");
+        assert!(
+            fired.recv_timeout(Duration::from_millis(100)).is_err(),
+            "Nothing fires while the group is disabled or paused"
+        );
+        group.resume();
+
+        assert_eq!(engine.press(VKey::F16), KeyAction::Block);
+        assert_eq!(next(), Ok("fire"));
+        group.unregister_all();
+        assert_eq!(next(), Ok("release"));
+        engine.advance(Duration::from_millis(500));
+        assert_eq!(engine.release(VKey::F16), KeyAction::Allow);
+        assert!(
+            fired.recv_timeout(Duration::from_millis(100)).is_err(),
+            "Unregistering the group stops the repeats"
+        );
+    }
+
+    #[test]
+    fn test_registry_is_unlocked_while_deciding() {
+        let mut engine = Engine::new();
+        let hkm = HotkeyManager::current();
+        let id = hkm
+            .register_hotkey(Hotkey::new(VKey::V, [VKey::Control], || {}).decide(|_| {
+                // deciding from the registry used to wait for the event loop
+                let registered = HotkeyManager::current().list_hotkeys();
+                if registered.iter().any(|spec| spec.trigger_key == VKey::V) {
+                    KeyAction::Block
+                } else {
+                    KeyAction::Allow
+                }
+            }))
+            .unwrap();
+
+        engine.press(VKey::LControl);
+        assert_eq!(engine.press(VKey::V), KeyAction::Block);
+        engine.release(VKey::V);
+        engine.release(VKey::LControl);
+
+        hkm.unregister_hotkey(id).unwrap();
+    }
+
+    #[test]
+    fn test_ties_across_trigger_keys_keep_registration_order() {
+        let names = |first: VKey, second: VKey| {
+            let mut registry = HotkeyRegistry::default();
+            for key in [first, second] {
+                let tap = Hotkey::modifier_tap(key, || {}).name(format!("{key:?}"));
+                registry.register(tap, Instant::now()).unwrap();
+            }
+            resolution_order(
+                registry
+                    .iter()
+                    .filter(|hotkey| hotkey.trigger_key.matches(&VKey::LControl)),
+                &[],
+            )
+            .into_iter()
+            .filter_map(|hotkey| hotkey.metadata.name.clone())
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(VKey::LControl, VKey::Control),
+            ["LControl", "Control"]
+        );
+        assert_eq!(
+            names(VKey::Control, VKey::LControl),
+            ["Control", "LControl"]
+        );
+    }
+
+    #[test]
+    fn test_unregister_unknown_hotkey() {
+        let id = HotkeyId::from(u64::MAX);
+        assert!(matches!(
+            HotkeyManager::current().unregister_hotkey(id),
+            Err(WHKError::HotkeyNotFound(missing)) if missing == id
+        ));
     }
 }
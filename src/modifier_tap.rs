@@ -0,0 +1,144 @@
+//! Detection of modifier keys (Win, Ctrl, Alt, Shift) pressed and released alone,
+//! used by [`TriggerTiming::ModifierTap`] hotkeys.
+//!
+//! [`TriggerTiming::ModifierTap`]: crate::TriggerTiming::ModifierTap
+
+use std::time::{Duration, Instant};
+
+use crate::VKey;
+
+#[derive(Debug)]
+struct ModifierPress {
+    key: VKey,
+    started: Instant,
+    clean: bool,
+}
+
+/// Tracks whether the last pressed modifier stays alone until it is released.
+#[derive(Debug)]
+pub(crate) struct ModifierTapTracker {
+    press: Option<ModifierPress>,
+}
+
+impl ModifierTapTracker {
+    pub const fn new() -> Self {
+        Self { press: None }
+    }
+
+    /// Registers a fresh key down (auto-repeats must not be reported).
+    ///
+    /// `alone` tells if no other key was down when `key` was pressed, only a
+    /// modifier pressed alone can become a tap.
+    pub fn key_down(&mut self, key: VKey, alone: bool, now: Instant) {
+        if alone && key.is_modifier_key() {
+            self.press = Some(ModifierPress {
+                key,
+                started: now,
+                clean: true,
+            });
+        } else {
+            self.interrupt();
+        }
+    }
+
+    /// Marks the current press as not alone, e.g. due to a mouse click.
+    pub fn interrupt(&mut self) {
+        if let Some(press) = self.press.as_mut() {
+            press.clean = false;
+        }
+    }
+
+    /// Registers a key release, returns how long the modifier was held if it
+    /// was pressed and released alone.
+    pub fn key_up(&mut self, key: VKey, now: Instant) -> Option<Duration> {
+        let press = self.press.take_if(|press| press.key == key)?;
+        if !press.clean {
+            return None;
+        }
+        Some(now.saturating_duration_since(press.started))
+    }
+
+    /// Whether a modifier is held alone, a mouse click would interrupt it.
+    pub fn is_pending(&self) -> bool {
+        self.press.as_ref().is_some_and(|press| press.clean)
+    }
+
+    pub fn clear(&mut self) {
+        self.press = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    #[test]
+    fn test_modifier_pressed_alone() {
+        let clock = ManualClock::new();
+        let mut tracker = ModifierTapTracker::new();
+
+        tracker.key_down(VKey::LWin, true, clock.now());
+        clock.advance(Duration::from_millis(120));
+        assert_eq!(
+            tracker.key_up(VKey::LWin, clock.now()),
+            Some(Duration::from_millis(120))
+        );
+        assert_eq!(
+            tracker.key_up(VKey::LWin, clock.now()),
+            None,
+            "A tap should only be reported once"
+        );
+    }
+
+    #[test]
+    fn test_other_key_during_press() {
+        let clock = ManualClock::new();
+        let mut tracker = ModifierTapTracker::new();
+
+        tracker.key_down(VKey::LControl, true, clock.now());
+        tracker.key_down(VKey::C, false, clock.now());
+        assert_eq!(tracker.key_up(VKey::LControl, clock.now()), None);
+
+        tracker.key_down(VKey::LControl, true, clock.now());
+        tracker.key_down(VKey::LShift, false, clock.now());
+        assert_eq!(
+            tracker.key_up(VKey::LControl, clock.now()),
+            None,
+            "Another modifier also breaks the tap"
+        );
+    }
+
+    #[test]
+    fn test_mouse_click_during_press() {
+        let clock = ManualClock::new();
+        let mut tracker = ModifierTapTracker::new();
+
+        assert!(!tracker.is_pending());
+        tracker.key_down(VKey::LMenu, true, clock.now());
+        assert!(tracker.is_pending(), "A click would change the outcome");
+        tracker.interrupt();
+        assert!(!tracker.is_pending());
+        assert_eq!(tracker.key_up(VKey::LMenu, clock.now()), None);
+    }
+
+    #[test]
+    fn test_modifier_not_pressed_alone() {
+        let clock = ManualClock::new();
+        let mut tracker = ModifierTapTracker::new();
+
+        // A is held while Win is pressed and released
+        tracker.key_down(VKey::A, true, clock.now());
+        tracker.key_down(VKey::LWin, false, clock.now());
+        assert_eq!(tracker.key_up(VKey::LWin, clock.now()), None);
+    }
+
+    #[test]
+    fn test_non_modifier_is_never_a_tap() {
+        let clock = ManualClock::new();
+        let mut tracker = ModifierTapTracker::new();
+
+        tracker.key_down(VKey::A, true, clock.now());
+        assert_eq!(tracker.key_up(VKey::A, clock.now()), None);
+    }
+}
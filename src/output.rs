@@ -0,0 +1,509 @@
+//! Text output by key injection, e.g. to replace a typed abbreviation.
+//!
+//! Text is typed with `KEYEVENTF_UNICODE` events, so any character can be sent
+//! whatever the keyboard layout, including emoji. The events go through an
+//! [`InputSink`], which is [`SendInputSink`] by default and a [`RecordingSink`]
+//! on tests, to check the exact injected sequence.
+//!
+//! Long texts are sent in chunks, with a short delay in between, so the input
+//! queue of the focused application doesn't overflow.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+    KEYEVENTF_UNICODE, VIRTUAL_KEY,
+};
+
+use crate::error::{Result, WHKError};
+use crate::hook::INJECTED_TAG;
+use crate::VKey;
+
+/// Default maximum number of events sent at once.
+pub const DEFAULT_CHUNK_SIZE: usize = 64;
+
+/// Default delay between two chunks.
+pub const DEFAULT_CHUNK_DELAY: Duration = Duration::from_millis(5);
+
+/// A single injected keyboard event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputEvent {
+    /// A virtual key press or release.
+    Key { key: VKey, down: bool },
+    /// A UTF-16 code unit typed as is, regardless of the keyboard layout.
+    Unicode { unit: u16, down: bool },
+}
+
+/// Destination of the injected keyboard events.
+pub trait InputSink: Send + Sync {
+    /// Injects the events in order, as a single batch.
+    fn send(&self, events: &[InputEvent]) -> Result<()>;
+}
+
+/// Injects the events with `SendInput`, the keyboard hook ignores them.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SendInputSink;
+
+impl InputSink for SendInputSink {
+    fn send(&self, events: &[InputEvent]) -> Result<()> {
+        let inputs: Vec<INPUT> = events
+            .iter()
+            .map(|event| {
+                let (vk, scan, flags, down) = match *event {
+                    InputEvent::Key { key, down } => {
+                        (VIRTUAL_KEY(key.to_vk_code()), 0, KEYBD_EVENT_FLAGS(0), down)
+                    }
+                    InputEvent::Unicode { unit, down } => {
+                        (VIRTUAL_KEY(0), unit, KEYEVENTF_UNICODE, down)
+                    }
+                };
+                INPUT {
+                    r#type: INPUT_KEYBOARD,
+                    Anonymous: INPUT_0 {
+                        ki: KEYBDINPUT {
+                            wVk: vk,
+                            wScan: scan,
+                            dwFlags: if down { flags } else { flags | KEYEVENTF_KEYUP },
+                            time: 0,
+                            dwExtraInfo: INJECTED_TAG,
+                        },
+                    },
+                }
+            })
+            .collect();
+        let sent = unsafe { SendInput(&inputs, size_of::<INPUT>() as i32) };
+        if sent as usize != inputs.len() {
+            return Err(WHKError::InputInjectionFailed);
+        }
+        Ok(())
+    }
+}
+
+/// Records the events instead of injecting them, intended for tests.
+///
+/// Clones share the same record, so a clone can be given to a [`TextOutput`]
+/// while the test keeps checking the original.
+#[derive(Debug, Default, Clone)]
+pub struct RecordingSink {
+    batches: Arc<Mutex<Vec<Vec<InputEvent>>>>,
+}
+
+impl RecordingSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the batches received so far.
+    pub fn batches(&self) -> Vec<Vec<InputEvent>> {
+        self.batches.lock().unwrap().clone()
+    }
+
+    /// Returns all the events received so far, in order.
+    pub fn events(&self) -> Vec<InputEvent> {
+        self.batches.lock().unwrap().concat()
+    }
+
+    pub fn clear(&self) {
+        self.batches.lock().unwrap().clear();
+    }
+}
+
+impl InputSink for RecordingSink {
+    fn send(&self, events: &[InputEvent]) -> Result<()> {
+        self.batches.lock().unwrap().push(events.to_vec());
+        Ok(())
+    }
+}
+
+/// A local date and time, used to fill the templates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LocalTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl LocalTime {
+    /// Returns the current local time of the system.
+    pub fn now() -> LocalTime {
+        let time = unsafe { windows::Win32::System::SystemInformation::GetLocalTime() };
+        LocalTime {
+            year: time.wYear,
+            month: time.wMonth as u8,
+            day: time.wDay as u8,
+            hour: time.wHour as u8,
+            minute: time.wMinute as u8,
+            second: time.wSecond as u8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+    Text(String),
+    Date,
+    Time,
+    Cursor,
+}
+
+/// A replacement text with placeholders:
+/// - `{date}`: the local date, as `YYYY-MM-DD`
+/// - `{time}`: the local time, as `HH:MM`
+/// - `{cursor}`: where the caret is left once the text is typed, at most once
+///
+/// Braces are escaped by doubling them, `{{` and `}}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextTemplate {
+    parts: Vec<TemplatePart>,
+}
+
+impl TextTemplate {
+    /// Parses a template, see [`TextTemplate`] for the placeholders.
+    pub fn parse(template: &str) -> Result<TextTemplate> {
+        let invalid = || WHKError::InvalidTemplate(template.to_owned());
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let (name, rest) = chars.as_str().split_once('}').ok_or_else(invalid)?;
+                    let part = match name {
+                        "date" => TemplatePart::Date,
+                        "time" => TemplatePart::Time,
+                        "cursor" if !parts.contains(&TemplatePart::Cursor) => TemplatePart::Cursor,
+                        _ => return Err(invalid()),
+                    };
+                    if !text.is_empty() {
+                        parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(part);
+                    chars = rest.chars();
+                }
+                '}' => return Err(invalid()),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(TemplatePart::Text(text));
+        }
+        Ok(TextTemplate { parts })
+    }
+
+    /// A template typing `text` as is.
+    pub fn text<S: Into<String>>(text: S) -> TextTemplate {
+        TextTemplate {
+            parts: vec![TemplatePart::Text(text.into())],
+        }
+    }
+
+    /// Returns the text to type and the number of characters the caret moves
+    /// back to reach the `{cursor}` placeholder.
+    pub fn render(&self, now: &LocalTime) -> (String, usize) {
+        let mut text = String::new();
+        let mut cursor = None;
+        for part in &self.parts {
+            match part {
+                TemplatePart::Text(part) => text.push_str(part),
+                TemplatePart::Date => {
+                    text.push_str(&format!("{:04}-{:02}-{:02}", now.year, now.month, now.day))
+                }
+                TemplatePart::Time => text.push_str(&format!("{:02}:{:02}", now.hour, now.minute)),
+                TemplatePart::Cursor => cursor = Some(text.len()),
+            }
+        }
+        let back = cursor.map_or(0, |cursor| caret_moves(&text[cursor..]));
+        (text, back)
+    }
+
+    /// Appends text after the other parts.
+    pub(crate) fn push_text(&mut self, text: &str) {
+        self.parts.push(TemplatePart::Text(text.to_owned()));
+    }
+}
+
+impl FromStr for TextTemplate {
+    type Err = WHKError;
+
+    fn from_str(template: &str) -> Result<Self> {
+        TextTemplate::parse(template)
+    }
+}
+
+impl fmt::Display for TextTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for part in &self.parts {
+            match part {
+                TemplatePart::Text(text) => {
+                    write!(f, "{}", text.replace('{', "{{").replace('}', "}}"))?
+                }
+                TemplatePart::Date => write!(f, "{{date}}")?,
+                TemplatePart::Time => write!(f, "{{time}}")?,
+                TemplatePart::Cursor => write!(f, "{{cursor}}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Number of `LEFT` presses moving the caret back over `text`.
+fn caret_moves(text: &str) -> usize {
+    text.chars().filter(|c| *c != '\r').count()
+}
+
+/// Types text through an [`InputSink`], in chunks.
+#[derive(Clone)]
+pub struct TextOutput {
+    sink: Arc<dyn InputSink>,
+    chunk_size: usize,
+    chunk_delay: Duration,
+}
+
+impl TextOutput {
+    pub fn new<S: InputSink + 'static>(sink: S) -> Self {
+        Self {
+            sink: Arc::new(sink),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            chunk_delay: DEFAULT_CHUNK_DELAY,
+        }
+    }
+
+    /// Sets the maximum number of events sent at once, the events of a single
+    /// character are never split.
+    pub fn chunk_size(mut self, events: usize) -> Self {
+        self.chunk_size = events.max(1);
+        self
+    }
+
+    /// Sets the delay between two chunks.
+    pub fn chunk_delay(mut self, delay: Duration) -> Self {
+        self.chunk_delay = delay;
+        self
+    }
+
+    /// Types `text`, `\n` and `\t` are sent as `ENTER` and `TAB` presses.
+    pub fn send_text(&self, text: &str) -> Result<()> {
+        self.send(text.chars().filter_map(char_events).collect())
+    }
+
+    /// Erases `count` characters before the caret.
+    pub fn send_backspaces(&self, count: usize) -> Result<()> {
+        self.send(vec![key_press(VKey::Back); count])
+    }
+
+    /// Erases `erase` characters before the caret, then types the template
+    /// filled with the current local time.
+    pub fn replace(&self, erase: usize, template: &TextTemplate) -> Result<()> {
+        self.replace_at(erase, template, &LocalTime::now())
+    }
+
+    /// Same as [`TextOutput::replace`], with the time used to fill the template.
+    pub fn replace_at(&self, erase: usize, template: &TextTemplate, now: &LocalTime) -> Result<()> {
+        let (text, back) = template.render(now);
+        let mut presses = vec![key_press(VKey::Back); erase];
+        presses.extend(text.chars().filter_map(char_events));
+        presses.extend(std::iter::repeat_n(key_press(VKey::Left), back));
+        self.send(presses)
+    }
+
+    /// Sends the presses, packed into chunks of at most `chunk_size` events.
+    fn send(&self, presses: Vec<Vec<InputEvent>>) -> Result<()> {
+        let mut chunk: Vec<InputEvent> = Vec::new();
+        let mut first = true;
+        for press in presses {
+            if !chunk.is_empty() && chunk.len() + press.len() > self.chunk_size {
+                self.send_chunk(&chunk, &mut first)?;
+                chunk.clear();
+            }
+            chunk.extend(press);
+        }
+        if !chunk.is_empty() {
+            self.send_chunk(&chunk, &mut first)?;
+        }
+        Ok(())
+    }
+
+    fn send_chunk(&self, chunk: &[InputEvent], first: &mut bool) -> Result<()> {
+        if !*first && !self.chunk_delay.is_zero() {
+            std::thread::sleep(self.chunk_delay);
+        }
+        *first = false;
+        self.sink.send(chunk)
+    }
+}
+
+impl Default for TextOutput {
+    fn default() -> Self {
+        TextOutput::new(SendInputSink)
+    }
+}
+
+impl fmt::Debug for TextOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TextOutput")
+            .field("chunk_size", &self.chunk_size)
+            .field("chunk_delay", &self.chunk_delay)
+            .finish()
+    }
+}
+
+fn key_press(key: VKey) -> Vec<InputEvent> {
+    vec![
+        InputEvent::Key { key, down: true },
+        InputEvent::Key { key, down: false },
+    ]
+}
+
+/// Returns the events typing `c`, a press and a release of each UTF-16 code unit.
+fn char_events(c: char) -> Option<Vec<InputEvent>> {
+    match c {
+        '\r' => None,
+        '\n' => Some(key_press(VKey::Return)),
+        '\t' => Some(key_press(VKey::Tab)),
+        c => Some(
+            c.encode_utf16(&mut [0; 2])
+                .iter()
+                .flat_map(|unit| {
+                    [
+                        InputEvent::Unicode {
+                            unit: *unit,
+                            down: true,
+                        },
+                        InputEvent::Unicode {
+                            unit: *unit,
+                            down: false,
+                        },
+                    ]
+                })
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output() -> (TextOutput, RecordingSink) {
+        let sink = RecordingSink::new();
+        let output = TextOutput::new(sink.clone()).chunk_delay(Duration::ZERO);
+        (output, sink)
+    }
+
+    fn unicode(units: &[u16]) -> Vec<InputEvent> {
+        units
+            .iter()
+            .flat_map(|unit| {
+                [
+                    InputEvent::Unicode {
+                        unit: *unit,
+                        down: true,
+                    },
+                    InputEvent::Unicode {
+                        unit: *unit,
+                        down: false,
+                    },
+                ]
+            })
+            .collect()
+    }
+
+    const TIME: LocalTime = LocalTime {
+        year: 2024,
+        month: 3,
+        day: 9,
+        hour: 7,
+        minute: 5,
+        second: 0,
+    };
+
+    #[test]
+    fn test_unicode_text() {
+        let (output, sink) = output();
+        output.send_text("é😀").unwrap();
+        assert_eq!(sink.events(), unicode(&[0xE9, 0xD83D, 0xDE00]));
+    }
+
+    #[test]
+    fn test_line_breaks_are_key_presses() {
+        let (output, sink) = output();
+        output.send_text("a\r\n\t").unwrap();
+
+        let mut expected = unicode(&['a' as u16]);
+        expected.extend(key_press(VKey::Return));
+        expected.extend(key_press(VKey::Tab));
+        assert_eq!(sink.events(), expected);
+    }
+
+    #[test]
+    fn test_chunks_keep_characters_whole() {
+        let (output, sink) = output();
+        let output = output.chunk_size(6);
+        output.send_text("ab😀c").unwrap();
+
+        let batches = sink.batches();
+        assert_eq!(
+            batches.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![4, 6],
+            "The surrogate pair of the emoji is sent in the same chunk"
+        );
+        assert_eq!(
+            batches.concat(),
+            unicode(&[0x61, 0x62, 0xD83D, 0xDE00, 0x63])
+        );
+    }
+
+    #[test]
+    fn test_replace() {
+        let (output, sink) = output();
+        let template = TextTemplate::parse("<b>{cursor}</b>").unwrap();
+        output.replace_at(2, &template, &TIME).unwrap();
+
+        let mut expected = [key_press(VKey::Back), key_press(VKey::Back)].concat();
+        expected.extend(unicode(&"<b></b>".encode_utf16().collect::<Vec<_>>()));
+        for _ in 0..4 {
+            expected.extend(key_press(VKey::Left));
+        }
+        assert_eq!(sink.events(), expected);
+    }
+
+    #[test]
+    fn test_template_placeholders() {
+        let template = TextTemplate::parse("{{{date}}} at {time}").unwrap();
+        assert_eq!(
+            template.render(&TIME),
+            ("{2024-03-09} at 07:05".to_owned(), 0)
+        );
+        assert_eq!(template.to_string(), "{{{date}}} at {time}");
+
+        let template = TextTemplate::parse("Dear {cursor},\nRegards").unwrap();
+        assert_eq!(template.render(&TIME).1, 9);
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        for template in ["{day}", "{date", "}", "{cursor}{cursor}"] {
+            assert!(
+                matches!(
+                    TextTemplate::parse(template),
+                    Err(WHKError::InvalidTemplate(_))
+                ),
+                "{template}"
+            );
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Reasons the hotkeys are paused for.
+//!
+//! Hotkeys are paused while at least one reason is active: the manual pause of
+//! [`HotkeysPauseHandler::set`], a [`PauseGuard`] not dropped yet, or a timed
+//! pause not elapsed yet. Independent parts of an application can each pause the
+//! hotkeys without resuming them behind the back of the others.
+//!
+//! [`HotkeysPauseHandler::set`]: crate::HotkeysPauseHandler::set
+
+use std::time::Instant;
+
+use crate::HotkeysPauseHandler;
+
+/// Notification of a change of the pause state, see
+/// [`crate::HotkeyManager::set_pause_listener`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PauseChange {
+    pub paused: bool,
+    /// the active pause reasons, the manual pause excepted
+    pub reasons: Vec<String>,
+}
+
+/// Keeps the hotkeys paused until dropped, see [`HotkeysPauseHandler::pause`].
+#[derive(Debug)]
+#[must_use = "the hotkeys resume as soon as the guard is dropped"]
+pub struct PauseGuard {
+    pub(crate) token: u64,
+}
+
+impl Drop for PauseGuard {
+    fn drop(&mut self) {
+        HotkeysPauseHandler::current().resume_token(self.token);
+    }
+}
+
+#[derive(Debug)]
+struct PauseReason {
+    token: u64,
+    reason: String,
+    /// end of a timed pause
+    until: Option<Instant>,
+}
+
+#[derive(Debug)]
+pub(crate) struct PauseState {
+    manual: bool,
+    reasons: Vec<PauseReason>,
+    next_token: u64,
+}
+
+impl PauseState {
+    pub const fn new() -> Self {
+        Self {
+            manual: false,
+            reasons: Vec::new(),
+            next_token: 0,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.manual || !self.reasons.is_empty()
+    }
+
+    pub fn is_manual(&self) -> bool {
+        self.manual
+    }
+
+    /// The active reasons, oldest first.
+    pub fn reasons(&self) -> Vec<String> {
+        self.reasons
+            .iter()
+            .map(|reason| reason.reason.clone())
+            .collect()
+    }
+
+    pub fn set_manual(&mut self, paused: bool) {
+        self.manual = paused;
+    }
+
+    /// Adds a reason, until `until` if set, returns its token.
+    pub fn add(&mut self, reason: String, until: Option<Instant>) -> u64 {
+        self.next_token += 1;
+        self.reasons.push(PauseReason {
+            token: self.next_token,
+            reason,
+            until,
+        });
+        self.next_token
+    }
+
+    pub fn remove(&mut self, token: u64) {
+        self.reasons.retain(|reason| reason.token != token);
+    }
+
+    /// Removes the timed reasons elapsed at `now`.
+    pub fn expire(&mut self, now: Instant) {
+        self.reasons
+            .retain(|reason| reason.until.is_none_or(|until| until > now));
+    }
+
+    /// Ends of the timed reasons.
+    pub fn deadlines(&self) -> Vec<Instant> {
+        self.reasons
+            .iter()
+            .filter_map(|reason| reason.until)
+            .collect()
+    }
+
+    /// Builds the notification of the current state.
+    pub fn change(&self) -> PauseChange {
+        PauseChange {
+            paused: self.is_paused(),
+            reasons: self.reasons(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_paused_until_all_reasons_end() {
+        let mut state = PauseState::new();
+        let menu = state.add("menu".to_owned(), None);
+        let dialog = state.add("dialog".to_owned(), None);
+        assert!(state.is_paused());
+        assert_eq!(state.reasons(), ["menu", "dialog"]);
+
+        state.remove(menu);
+        assert!(state.is_paused());
+        state.remove(dialog);
+        assert!(!state.is_paused());
+    }
+
+    #[test]
+    fn test_manual_pause() {
+        let mut state = PauseState::new();
+        let token = state.add("menu".to_owned(), None);
+        state.set_manual(true);
+        state.remove(token);
+        assert!(state.is_paused());
+        assert!(state.reasons().is_empty());
+
+        state.set_manual(false);
+        assert!(!state.is_paused());
+    }
+
+    #[test]
+    fn test_timed_pause() {
+        let now = Instant::now();
+        let mut state = PauseState::new();
+        state.add("timed".to_owned(), Some(now + Duration::from_secs(5)));
+        state.add("menu".to_owned(), None);
+
+        state.expire(now + Duration::from_secs(4));
+        assert_eq!(state.reasons(), ["timed", "menu"]);
+        state.expire(now + Duration::from_secs(5));
+        assert_eq!(state.reasons(), ["menu"]);
+    }
+}
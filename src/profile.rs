@@ -0,0 +1,396 @@
+//! Named sets of hotkeys, e.g. for "editing", "presentation" or "gaming".
+//!
+//! Profiles are prepared in advance and at most one of them is active at a time.
+//! Activating a profile swaps its hotkeys with the ones of the previous profile in
+//! a single update of the registry, so the event loop never sees a partial set.
+//! The hotkeys registered with [`crate::HotkeyManager::register_hotkey`] form the
+//! global profile, which stays active alongside any profile.
+
+use std::time::Instant;
+
+use crate::error::{Result, WHKError};
+use crate::hotkey::{Hotkey, HotkeyId};
+use crate::registry::HotkeyRegistry;
+
+/// A named set of hotkeys, see [`crate::HotkeyManager::add_profile`].
+#[derive(Debug)]
+pub struct HotkeyProfile {
+    name: String,
+    hotkeys: Vec<Hotkey>,
+}
+
+impl HotkeyProfile {
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            hotkeys: Vec::new(),
+        }
+    }
+
+    /// Adds a hotkey to the profile.
+    pub fn hotkey(mut self, hotkey: Hotkey) -> Self {
+        self.hotkeys.push(hotkey);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The hotkeys of the profile, in registration order.
+    pub fn hotkeys(&self) -> &[Hotkey] {
+        &self.hotkeys
+    }
+}
+
+#[derive(Debug)]
+struct ActiveProfile {
+    name: String,
+    /// ids of the profile hotkeys in the registry, with the ids supplied by the user
+    ids: Vec<(HotkeyId, Option<HotkeyId>)>,
+}
+
+impl ActiveProfile {
+    /// Takes the hotkeys of the profile out of the registry, with the ids supplied
+    /// by the user. Those unregistered in the meantime are gone for good.
+    fn take(&self, registry: &mut HotkeyRegistry) -> Vec<(Hotkey, Option<HotkeyId>)> {
+        self.ids
+            .iter()
+            .filter_map(|(id, supplied)| Some((registry.unregister(*id)?, *supplied)))
+            .collect()
+    }
+
+    /// Turns the hotkeys taken out of the registry back into a profile, their
+    /// allocated ids are released.
+    fn into_profile(self, hotkeys: Vec<(Hotkey, Option<HotkeyId>)>) -> HotkeyProfile {
+        HotkeyProfile {
+            name: self.name,
+            hotkeys: hotkeys
+                .into_iter()
+                .map(|(mut hotkey, supplied)| {
+                    hotkey.id = supplied;
+                    hotkey
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Profiles {
+    /// profiles that are not active
+    stored: Vec<HotkeyProfile>,
+    active: Option<ActiveProfile>,
+}
+
+impl Profiles {
+    pub const fn new() -> Self {
+        Self {
+            stored: Vec::new(),
+            active: None,
+        }
+    }
+
+    pub fn active(&self) -> Option<&str> {
+        self.active.as_ref().map(|active| active.name.as_str())
+    }
+
+    /// Ids of the hotkeys of the active profile in the registry.
+    pub fn active_ids(&self) -> Vec<HotkeyId> {
+        self.active
+            .iter()
+            .flat_map(|active| active.ids.iter().map(|(id, _)| *id))
+            .collect()
+    }
+
+    /// Names of the profiles, the active one included, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .stored
+            .iter()
+            .map(|profile| profile.name.clone())
+            .chain(self.active().map(str::to_owned))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Adds a profile, replacing the one with the same name. Replacing the active
+    /// profile swaps its hotkeys in the registry, on failure the previous version
+    /// stays active.
+    pub fn insert(
+        &mut self,
+        profile: HotkeyProfile,
+        registry: &mut HotkeyRegistry,
+        now: Instant,
+    ) -> Result<()> {
+        check_profile(&profile)?;
+        if self.active() == Some(profile.name.as_str()) {
+            self.swap(registry, profile, now).map_err(|(_, err)| err)?;
+            return Ok(());
+        }
+        self.stored.retain(|stored| stored.name != profile.name);
+        self.stored.push(profile);
+        Ok(())
+    }
+
+    /// Activates the profile `name` in place of the active one. On failure the
+    /// active profile stays active.
+    pub fn activate(
+        &mut self,
+        name: &str,
+        registry: &mut HotkeyRegistry,
+        now: Instant,
+    ) -> Result<()> {
+        if self.active() == Some(name) {
+            return Ok(());
+        }
+        let position = self
+            .stored
+            .iter()
+            .position(|profile| profile.name == name)
+            .ok_or_else(|| WHKError::ProfileNotFound(name.to_owned()))?;
+
+        let profile = self.stored.remove(position);
+        match self.swap(registry, profile, now) {
+            Ok(previous) => {
+                self.stored.extend(previous);
+                Ok(())
+            }
+            Err((profile, err)) => {
+                self.stored.push(profile);
+                Err(err)
+            }
+        }
+    }
+
+    /// Deactivates the active profile, only the global profile stays active.
+    pub fn deactivate(&mut self, registry: &mut HotkeyRegistry) {
+        if let Some(active) = self.active.take() {
+            let hotkeys = active.take(registry);
+            self.stored.push(active.into_profile(hotkeys));
+        }
+    }
+
+    /// Removes the profile `name`, deactivating it if active.
+    pub fn remove(&mut self, name: &str, registry: &mut HotkeyRegistry) -> Option<HotkeyProfile> {
+        if self.active() == Some(name) {
+            let active = self.active.take()?;
+            let hotkeys = active.take(registry);
+            return Some(active.into_profile(hotkeys));
+        }
+        let position = self
+            .stored
+            .iter()
+            .position(|profile| profile.name == name)?;
+        Some(self.stored.remove(position))
+    }
+
+    /// Replaces the hotkeys of the active profile with the ones of `profile`,
+    /// returns the previous profile. On failure the registry is left untouched
+    /// and `profile` is returned along with the error.
+    fn swap(
+        &mut self,
+        registry: &mut HotkeyRegistry,
+        profile: HotkeyProfile,
+        now: Instant,
+    ) -> std::result::Result<Option<HotkeyProfile>, (HotkeyProfile, WHKError)> {
+        let previous = self.active.take();
+        let taken = previous
+            .as_ref()
+            .map(|active| active.take(registry))
+            .unwrap_or_default();
+
+        let checked = check_profile(&profile).and_then(|_| {
+            profile
+                .hotkeys
+                .iter()
+                .try_for_each(|hotkey| registry.check(hotkey))
+        });
+        if let Err(err) = checked {
+            if let Some(previous) = previous {
+                let ids = taken
+                    .into_iter()
+                    .map(|(hotkey, supplied)| {
+                        let id = registry
+                            .restore(hotkey)
+                            .expect("restored hotkeys were registered");
+                        (id, supplied)
+                    })
+                    .collect();
+                self.active = Some(ActiveProfile { ids, ..previous });
+            }
+            return Err((profile, err));
+        }
+
+        let ids = profile
+            .hotkeys
+            .into_iter()
+            .map(|hotkey| {
+                let supplied = hotkey.id;
+                let id = registry
+                    .register(hotkey, now)
+                    .expect("profile hotkeys are checked");
+                (id, supplied)
+            })
+            .collect();
+        self.active = Some(ActiveProfile {
+            name: profile.name,
+            ids,
+        });
+        Ok(previous.map(|previous| previous.into_profile(taken)))
+    }
+}
+
+/// Checks that the hotkeys of a profile are valid and don't collide with each other.
+fn check_profile(profile: &HotkeyProfile) -> Result<()> {
+    let empty = HotkeyRegistry::default();
+    for (i, hotkey) in profile.hotkeys.iter().enumerate() {
+        empty.check(hotkey)?;
+        for other in &profile.hotkeys[..i] {
+            if hotkey == other {
+                return Err(WHKError::HotKeyAlreadyRegistered);
+            }
+            if let Some(id) = hotkey.id.filter(|id| other.id == Some(*id)) {
+                return Err(WHKError::HotkeyIdInUse(id));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VKey;
+
+    fn hotkey(key: VKey) -> Hotkey {
+        Hotkey::new(key, [VKey::Control], || {})
+    }
+
+    fn global() -> HotkeyRegistry {
+        let mut registry = HotkeyRegistry::default();
+        registry.register(hotkey(VKey::G), Instant::now()).unwrap();
+        registry
+    }
+
+    fn keys(registry: &HotkeyRegistry) -> Vec<VKey> {
+        registry.iter().map(|hotkey| hotkey.trigger_key).collect()
+    }
+
+    #[test]
+    fn test_switch_profiles() {
+        let mut registry = global();
+        let mut profiles = Profiles::new();
+        let now = Instant::now();
+        profiles
+            .insert(
+                HotkeyProfile::new("editing")
+                    .hotkey(hotkey(VKey::A))
+                    .hotkey(hotkey(VKey::S)),
+                &mut registry,
+                now,
+            )
+            .unwrap();
+        profiles
+            .insert(
+                HotkeyProfile::new("gaming").hotkey(hotkey(VKey::A)),
+                &mut registry,
+                now,
+            )
+            .unwrap();
+        assert_eq!(keys(&registry), [VKey::G], "Stored, not active");
+
+        profiles.activate("editing", &mut registry, now).unwrap();
+        assert_eq!(keys(&registry), [VKey::A, VKey::G, VKey::S]);
+        profiles.activate("gaming", &mut registry, now).unwrap();
+        assert_eq!(keys(&registry), [VKey::A, VKey::G]);
+        assert_eq!(profiles.active(), Some("gaming"));
+        assert_eq!(profiles.names(), ["editing", "gaming"]);
+
+        profiles.deactivate(&mut registry);
+        assert_eq!(keys(&registry), [VKey::G]);
+        assert!(matches!(
+            profiles.activate("presentation", &mut registry, now),
+            Err(WHKError::ProfileNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_failed_activation_keeps_active_profile() {
+        let mut registry = global();
+        let mut profiles = Profiles::new();
+        let now = Instant::now();
+        profiles
+            .insert(
+                HotkeyProfile::new("editing").hotkey(hotkey(VKey::A).id(10)),
+                &mut registry,
+                now,
+            )
+            .unwrap();
+        profiles
+            .insert(
+                HotkeyProfile::new("broken")
+                    .hotkey(hotkey(VKey::B))
+                    .hotkey(hotkey(VKey::G)),
+                &mut registry,
+                now,
+            )
+            .unwrap();
+        profiles.activate("editing", &mut registry, now).unwrap();
+
+        assert!(matches!(
+            profiles.activate("broken", &mut registry, now),
+            Err(WHKError::HotKeyAlreadyRegistered)
+        ));
+        assert_eq!(profiles.active(), Some("editing"));
+        assert_eq!(keys(&registry), [VKey::A, VKey::G]);
+        assert!(registry.get(HotkeyId::from(10)).is_some());
+        assert_eq!(profiles.names(), ["broken", "editing"]);
+    }
+
+    #[test]
+    fn test_profile_ids() {
+        let mut registry = global();
+        let mut profiles = Profiles::new();
+        let now = Instant::now();
+        profiles
+            .insert(
+                HotkeyProfile::new("editing")
+                    .hotkey(hotkey(VKey::A).id(10))
+                    .hotkey(hotkey(VKey::S)),
+                &mut registry,
+                now,
+            )
+            .unwrap();
+        profiles.activate("editing", &mut registry, now).unwrap();
+        profiles.deactivate(&mut registry);
+
+        // the allocated ids are released, the supplied ones are kept
+        let editing = profiles.remove("editing", &mut registry).unwrap();
+        let ids: Vec<Option<HotkeyId>> = editing.hotkeys().iter().map(|hotkey| hotkey.id).collect();
+        assert_eq!(ids, [Some(HotkeyId::from(10)), None]);
+    }
+
+    #[test]
+    fn test_invalid_profile() {
+        let mut registry = global();
+        let mut profiles = Profiles::new();
+        let duplicate = HotkeyProfile::new("editing")
+            .hotkey(hotkey(VKey::A))
+            .hotkey(hotkey(VKey::A).name("Again"));
+        assert!(matches!(
+            profiles.insert(duplicate, &mut registry, Instant::now()),
+            Err(WHKError::HotKeyAlreadyRegistered)
+        ));
+
+        let same_id = HotkeyProfile::new("editing")
+            .hotkey(hotkey(VKey::A).id(1))
+            .hotkey(hotkey(VKey::B).id(1));
+        assert!(matches!(
+            profiles.insert(same_id, &mut registry, Instant::now()),
+            Err(WHKError::HotkeyIdInUse(_))
+        ));
+        assert!(profiles.names().is_empty());
+    }
+}
@@ -0,0 +1,293 @@
+//! Storage of the registered hotkeys.
+//!
+//! Hotkeys are grouped by trigger key for the event loop lookups, each group
+//! keeping the registration order, and indexed by [`HotkeyId`].
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::error::{Result, WHKError};
+use crate::hotkey::{Hotkey, HotkeyId, TriggerTiming};
+use crate::usage::Usage;
+use crate::VKey;
+
+#[derive(Debug, Default)]
+pub(crate) struct HotkeyRegistry {
+    buckets: HashMap<VKey, Vec<Hotkey>>,
+    index: HashMap<HotkeyId, VKey>,
+    next_id: u64,
+    /// registrations so far, ranks the hotkeys across trigger keys
+    registrations: u64,
+}
+
+impl HotkeyRegistry {
+    pub fn with_hotkeys<I: IntoIterator<Item = Hotkey>>(hotkeys: I) -> Self {
+        let mut registry = HotkeyRegistry::default();
+        registry.reset(hotkeys);
+        registry
+    }
+
+    /// Replaces all the hotkeys by `hotkeys`, the ids given so far are not allocated again.
+    pub fn reset<I: IntoIterator<Item = Hotkey>>(&mut self, hotkeys: I) {
+        self.buckets.clear();
+        self.index.clear();
+        for hotkey in hotkeys {
+            self.insert(hotkey).expect("initial hotkeys must be valid");
+        }
+    }
+
+    /// Adds a hotkey, allocating an id if it doesn't have one, its uses and
+    /// expiration start from `now`.
+    pub fn register(&mut self, mut hotkey: Hotkey, now: Instant) -> Result<HotkeyId> {
+        let expires_at = hotkey.expires_after.map(|duration| now + duration);
+        hotkey.usage = Arc::new(Usage::new(hotkey.max_uses, expires_at));
+        self.insert(hotkey)
+    }
+
+    /// Adds back a hotkey taken out of the registry, keeping its uses and expiration.
+    pub fn restore(&mut self, hotkey: Hotkey) -> Result<HotkeyId> {
+        self.insert(hotkey)
+    }
+
+    /// Checks that the hotkey can be registered.
+    pub fn check(&self, hotkey: &Hotkey) -> Result<()> {
+        validate(hotkey)?;
+        if self.bucket(hotkey.trigger_key).contains(hotkey) {
+            return Err(WHKError::HotKeyAlreadyRegistered);
+        }
+        match hotkey.id {
+            Some(id) if self.index.contains_key(&id) => Err(WHKError::HotkeyIdInUse(id)),
+            _ => Ok(()),
+        }
+    }
+
+    fn insert(&mut self, mut hotkey: Hotkey) -> Result<HotkeyId> {
+        self.check(&hotkey)?;
+        let id = match hotkey.id {
+            Some(id) => id,
+            None => self.allocate_id(),
+        };
+
+        hotkey.id = Some(id);
+        self.registrations += 1;
+        hotkey.registration = self.registrations;
+        self.index.insert(id, hotkey.trigger_key);
+        self.buckets
+            .entry(hotkey.trigger_key)
+            .or_default()
+            .push(hotkey);
+        Ok(id)
+    }
+
+    pub fn unregister(&mut self, id: HotkeyId) -> Option<Hotkey> {
+        let key = self.index.remove(&id)?;
+        let bucket = self.buckets.get_mut(&key)?;
+        let position = bucket.iter().position(|hotkey| hotkey.id == Some(id))?;
+        let hotkey = bucket.remove(position);
+        if bucket.is_empty() {
+            self.buckets.remove(&key);
+        }
+        Some(hotkey)
+    }
+
+    /// Changes the keys of a hotkey, keeping its id and its other settings.
+    ///
+    /// The hotkey is registered last among the hotkeys of its new trigger key,
+    /// on failure it is left untouched.
+    pub fn rebind(&mut self, id: HotkeyId, trigger_key: VKey, modifiers: Vec<VKey>) -> Result<()> {
+        let current = self.get(id).ok_or(WHKError::HotkeyNotFound(id))?;
+        let mut rebound = Hotkey::new(trigger_key, &modifiers, || {})
+            .trigger_timing(current.trigger_timing)
+            .modifier_match(current.modifier_match)
+            .taps(current.taps, current.tap_window);
+        rebound.strict_sequence = current.strict_sequence;
+
+        validate(&rebound)?;
+        let taken = self
+            .bucket(trigger_key)
+            .iter()
+            .any(|hotkey| *hotkey == rebound && hotkey.id != Some(id));
+        if taken {
+            return Err(WHKError::HotKeyAlreadyRegistered);
+        }
+
+        let mut hotkey = self.unregister(id).ok_or(WHKError::HotkeyNotFound(id))?;
+        hotkey.trigger_key = trigger_key;
+        hotkey.modifiers = modifiers;
+        self.insert(hotkey).map(|_| ())
+    }
+
+    pub fn get(&self, id: HotkeyId) -> Option<&Hotkey> {
+        let key = self.index.get(&id)?;
+        self.bucket(*key)
+            .iter()
+            .find(|hotkey| hotkey.id == Some(id))
+    }
+
+    pub fn get_mut(&mut self, id: HotkeyId) -> Option<&mut Hotkey> {
+        let key = self.index.get(&id)?;
+        self.buckets
+            .get_mut(key)?
+            .iter_mut()
+            .find(|hotkey| hotkey.id == Some(id))
+    }
+
+    /// The hotkeys triggered by `key`, in registration order.
+    pub fn bucket(&self, key: VKey) -> &[Hotkey] {
+        self.buckets
+            .get(&key)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// All the hotkeys, grouped by trigger key code and in registration order.
+    pub fn iter(&self) -> impl Iterator<Item = &Hotkey> {
+        let mut keys: Vec<&VKey> = self.buckets.keys().collect();
+        keys.sort_by_key(|key| key.to_vk_code());
+        keys.into_iter().flat_map(|key| self.buckets[key].iter())
+    }
+
+    /// Returns an id that is not in use, ids are never 0.
+    fn allocate_id(&mut self) -> HotkeyId {
+        loop {
+            self.next_id += 1;
+            let id = HotkeyId(self.next_id);
+            if !self.index.contains_key(&id) {
+                return id;
+            }
+        }
+    }
+}
+
+fn validate(hotkey: &Hotkey) -> Result<()> {
+    if hotkey.trigger_key == VKey::None {
+        return Err(WHKError::HotkeyInvalidTriggerKey(hotkey.trigger_key));
+    }
+    if matches!(hotkey.trigger_timing, TriggerTiming::ModifierTap { .. })
+        && !hotkey.trigger_key.is_modifier_key()
+    {
+        return Err(WHKError::HotkeyInvalidTriggerKey(hotkey.trigger_key));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hotkey(key: VKey) -> Hotkey {
+        Hotkey::new(key, [VKey::Control], || {})
+    }
+
+    #[test]
+    fn test_ids_are_allocated() {
+        let mut registry = HotkeyRegistry::default();
+        let first = registry.register(hotkey(VKey::A), Instant::now()).unwrap();
+        let second = registry.register(hotkey(VKey::B), Instant::now()).unwrap();
+        assert_ne!(first, second);
+        assert_ne!(first, HotkeyId::default());
+        assert_eq!(registry.get(first).unwrap().trigger_key, VKey::A);
+    }
+
+    #[test]
+    fn test_user_supplied_ids() {
+        let mut registry = HotkeyRegistry::default();
+        let id = HotkeyId::from(1);
+        assert_eq!(
+            registry
+                .register(hotkey(VKey::A).id(id), Instant::now())
+                .unwrap(),
+            id
+        );
+        assert!(matches!(
+            registry.register(hotkey(VKey::B).id(id), Instant::now()),
+            Err(WHKError::HotkeyIdInUse(in_use)) if in_use == id
+        ));
+
+        // allocation skips the ids supplied by the user
+        assert_ne!(
+            registry.register(hotkey(VKey::C), Instant::now()).unwrap(),
+            id
+        );
+    }
+
+    #[test]
+    fn test_unregister() {
+        let mut registry = HotkeyRegistry::default();
+        let id = registry.register(hotkey(VKey::A), Instant::now()).unwrap();
+        assert!(registry.unregister(id).is_some());
+        assert!(registry.unregister(id).is_none());
+        assert!(registry.bucket(VKey::A).is_empty());
+
+        // the same combination can be registered again
+        assert!(registry.register(hotkey(VKey::A), Instant::now()).is_ok());
+    }
+
+    #[test]
+    fn test_reset_keeps_allocating_new_ids() {
+        let mut registry = HotkeyRegistry::default();
+        let id = registry.register(hotkey(VKey::A), Instant::now()).unwrap();
+
+        registry.reset([hotkey(VKey::B)]);
+        assert!(registry.get(id).is_none());
+        assert_eq!(registry.bucket(VKey::B).len(), 1);
+        assert_ne!(
+            registry.register(hotkey(VKey::A), Instant::now()).unwrap(),
+            id,
+            "A stale id must not point to a new hotkey"
+        );
+    }
+
+    #[test]
+    fn test_rebind_keeps_id() {
+        let mut registry = HotkeyRegistry::default();
+        let id = registry
+            .register(hotkey(VKey::A).name("Select all"), Instant::now())
+            .unwrap();
+
+        registry
+            .rebind(id, VKey::Q, vec![VKey::Control, VKey::Shift])
+            .unwrap();
+        let rebound = registry.get(id).unwrap();
+        assert_eq!(rebound.trigger_key, VKey::Q);
+        assert_eq!(rebound.modifiers, vec![VKey::Control, VKey::Shift]);
+        assert_eq!(rebound.metadata.name.as_deref(), Some("Select all"));
+        assert!(registry.bucket(VKey::A).is_empty());
+    }
+
+    #[test]
+    fn test_failed_rebind_keeps_binding() {
+        let mut registry = HotkeyRegistry::default();
+        let id = registry.register(hotkey(VKey::A), Instant::now()).unwrap();
+        registry.register(hotkey(VKey::B), Instant::now()).unwrap();
+
+        assert!(matches!(
+            registry.rebind(id, VKey::B, vec![VKey::Control]),
+            Err(WHKError::HotKeyAlreadyRegistered)
+        ));
+        assert!(matches!(
+            registry.rebind(id, VKey::None, vec![]),
+            Err(WHKError::HotkeyInvalidTriggerKey(VKey::None))
+        ));
+        assert_eq!(registry.get(id).unwrap().trigger_key, VKey::A);
+        assert!(matches!(
+            registry.rebind(HotkeyId::from(99), VKey::C, vec![]),
+            Err(WHKError::HotkeyNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_disabled_hotkey_keeps_its_binding() {
+        let mut registry = HotkeyRegistry::default();
+        let id = registry.register(hotkey(VKey::A), Instant::now()).unwrap();
+
+        registry.get_mut(id).unwrap().enabled = false;
+        assert!(matches!(
+            registry.register(hotkey(VKey::A), Instant::now()),
+            Err(WHKError::HotKeyAlreadyRegistered)
+        ));
+        registry.rebind(id, VKey::B, vec![VKey::Control]).unwrap();
+        assert!(!registry.get(id).unwrap().enabled);
+    }
+}
@@ -0,0 +1,194 @@
+//! Key remapping, e.g. making `CAPSLOCK` behave as `ESCAPE`.
+//!
+//! Remaps are applied by the hook before the keyboard state is updated, so the
+//! event loop only sees the target key and remapped keys can trigger hotkeys.
+//! The source key event is blocked and the target key event is injected instead,
+//! unless a hotkey blocks it. The release of the target key is only injected if
+//! its press was.
+//!
+//! The target of each press is recorded until its release, so a press and its
+//! release always concern the same target key, even if the remaps change in between.
+
+use std::sync::Mutex;
+
+use crate::VKey;
+
+pub(crate) static REMAPS: Mutex<RemapTable> = Mutex::new(RemapTable::new());
+
+#[derive(Debug)]
+pub(crate) struct RemapTable {
+    /// source and target keys, a generic `SHIFT`, `CTRL` or `ALT` source matches either side
+    remaps: Vec<(VKey, VKey)>,
+    /// pressed source keys
+    pressed: Vec<RemappedPress>,
+}
+
+#[derive(Debug)]
+struct RemappedPress {
+    key: VKey,
+    target: VKey,
+    /// whether the target key down was injected
+    injected: bool,
+}
+
+impl RemapTable {
+    pub const fn new() -> Self {
+        Self {
+            remaps: Vec::new(),
+            pressed: Vec::new(),
+        }
+    }
+
+    /// Remaps `source` to `target`, replacing any remap of `source`.
+    pub fn set(&mut self, source: VKey, target: VKey) {
+        self.remove(source);
+        self.remaps.push((source, target));
+    }
+
+    pub fn remove(&mut self, source: VKey) {
+        self.remaps.retain(|(remapped, _)| *remapped != source);
+    }
+
+    pub fn clear(&mut self) {
+        self.remaps.clear();
+    }
+
+    pub fn remaps(&self) -> Vec<(VKey, VKey)> {
+        self.remaps.clone()
+    }
+
+    /// Returns the target of a press of `key`, if remapped. An auto-repeated press
+    /// keeps the target of the first press.
+    ///
+    /// A remap of the exact key wins over a remap of its generic `SHIFT`, `CTRL` or
+    /// `ALT` key, the other keys are only remapped by their own remap.
+    pub fn key_down(&mut self, key: VKey) -> Option<VKey> {
+        if let Some(press) = self.pressed.iter().find(|press| press.key == key) {
+            return Some(press.target);
+        }
+        let (_, target) = *self
+            .remaps
+            .iter()
+            .find(|(source, _)| *source == key)
+            .or_else(|| {
+                self.remaps.iter().find(|(source, _)| {
+                    matches!(source, VKey::Shift | VKey::Control | VKey::Menu)
+                        && source.matches(&key)
+                })
+            })?;
+        self.pressed.push(RemappedPress {
+            key,
+            target,
+            injected: false,
+        });
+        Some(target)
+    }
+
+    /// Records that the target key down of the press of `key` was injected.
+    pub fn injected(&mut self, key: VKey) {
+        if let Some(press) = self.pressed.iter_mut().find(|press| press.key == key) {
+            press.injected = true;
+        }
+    }
+
+    /// Returns the target of the press released by `key`, if it was remapped, and
+    /// whether its key down was injected.
+    pub fn key_up(&mut self, key: VKey) -> Option<(VKey, bool)> {
+        let position = self.pressed.iter().position(|press| press.key == key)?;
+        let press = self.pressed.remove(position);
+        Some((press.target, press.injected))
+    }
+
+    /// Forgets the pressed keys, e.g. when the hook restarts.
+    pub fn release_all(&mut self) {
+        self.pressed.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remap() {
+        let mut table = RemapTable::new();
+        table.set(VKey::Capital, VKey::Escape);
+
+        assert_eq!(table.key_down(VKey::Capital), Some(VKey::Escape));
+        assert_eq!(table.key_up(VKey::Capital), Some((VKey::Escape, false)));
+        assert_eq!(table.key_down(VKey::A), None);
+        assert_eq!(table.key_up(VKey::A), None);
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut table = RemapTable::new();
+        table.set(VKey::LMenu, VKey::LWin);
+        table.set(VKey::LWin, VKey::LMenu);
+
+        assert_eq!(table.key_down(VKey::LMenu), Some(VKey::LWin));
+        assert_eq!(table.key_down(VKey::LWin), Some(VKey::LMenu));
+    }
+
+    #[test]
+    fn test_injected_press() {
+        let mut table = RemapTable::new();
+        table.set(VKey::Capital, VKey::Escape);
+
+        assert_eq!(table.key_down(VKey::Capital), Some(VKey::Escape));
+        table.injected(VKey::Capital);
+        assert_eq!(table.key_up(VKey::Capital), Some((VKey::Escape, true)));
+    }
+
+    #[test]
+    fn test_generic_source() {
+        let mut table = RemapTable::new();
+        table.set(VKey::Control, VKey::F13);
+        assert_eq!(table.key_down(VKey::RControl), Some(VKey::F13));
+
+        table.set(VKey::LControl, VKey::F14);
+        assert_eq!(
+            table.key_down(VKey::LControl),
+            Some(VKey::F14),
+            "The exact key wins"
+        );
+    }
+
+    #[test]
+    fn test_side_specific_source() {
+        let mut table = RemapTable::new();
+        table.set(VKey::LWin, VKey::F13);
+        assert_eq!(table.key_down(VKey::RWin), None);
+        assert_eq!(table.key_down(VKey::LWin), Some(VKey::F13));
+    }
+
+    #[test]
+    fn test_change_while_pressed() {
+        let mut table = RemapTable::new();
+        table.set(VKey::Capital, VKey::Escape);
+        assert_eq!(table.key_down(VKey::Capital), Some(VKey::Escape));
+
+        table.set(VKey::Capital, VKey::F13);
+        assert_eq!(
+            table.key_down(VKey::Capital),
+            Some(VKey::Escape),
+            "Auto-repeat keeps the target"
+        );
+        assert_eq!(
+            table.key_up(VKey::Capital),
+            Some((VKey::Escape, false)),
+            "Release matches the press"
+        );
+        assert_eq!(table.key_down(VKey::Capital), Some(VKey::F13));
+
+        table.clear();
+        assert_eq!(table.key_up(VKey::Capital), Some((VKey::F13, false)));
+        assert_eq!(table.key_down(VKey::Capital), None);
+        table.set(VKey::Capital, VKey::Escape);
+        assert_eq!(
+            table.key_up(VKey::Capital),
+            None,
+            "Press was not remapped, nor is its release"
+        );
+    }
+}
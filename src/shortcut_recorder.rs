@@ -0,0 +1,324 @@
+//! Recording of a new shortcut from the user, e.g. in a settings UI.
+//!
+//! The [`ShortcutRecorder`] builds the combination from the largest set of keys
+//! held at once, and completes when its trigger key is released. `ESCAPE`
+//! cancels the recording and `BACKSPACE` starts it over.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use crate::conflicts::{reserved_overlap, HotkeySpec};
+use crate::error::ShortcutError;
+use crate::events::KeyboardInputEvent;
+use crate::VKey;
+
+/// Builds a shortcut from keyboard events.
+#[derive(Debug, Clone, Default)]
+pub struct ShortcutRecorder {
+    /// keys currently held, in press order
+    pressed: Vec<VKey>,
+    /// largest set of keys held at once, in press order
+    peak: Vec<VKey>,
+    /// shortcuts rejected on top of the system shortcuts
+    reserved: Vec<HotkeySpec>,
+}
+
+impl ShortcutRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rejects the shortcuts overlapping `spec`, on top of the system shortcuts.
+    pub fn reserve(mut self, spec: HotkeySpec) -> Self {
+        self.reserved.push(spec);
+        self
+    }
+
+    /// Keys of the combination recorded so far, in press order.
+    pub fn keys(&self) -> &[VKey] {
+        &self.peak
+    }
+
+    /// Forgets the keys recorded so far.
+    pub fn clear(&mut self) {
+        self.peak.clear();
+    }
+
+    /// Feeds an event, returns the shortcut once recorded.
+    ///
+    /// The recorder starts over after returning a shortcut or an error.
+    pub fn handle(
+        &mut self,
+        event: &KeyboardInputEvent,
+    ) -> Option<Result<HotkeySpec, ShortcutError>> {
+        match event {
+            KeyboardInputEvent::KeyDown { key, .. } => self.key_down(*key),
+            KeyboardInputEvent::KeyUp { key, .. } => self.key_up(*key),
+        }
+    }
+
+    fn key_down(&mut self, key: VKey) -> Option<Result<HotkeySpec, ShortcutError>> {
+        if self.pressed.contains(&key) {
+            // auto-repeat
+            return None;
+        }
+        match key {
+            VKey::Escape => {
+                self.reset();
+                Some(Err(ShortcutError::Cancelled))
+            }
+            VKey::Back => {
+                self.clear();
+                None
+            }
+            key => {
+                self.pressed.push(key);
+                if self.pressed.len() >= self.peak.len() {
+                    self.peak = self.pressed.clone();
+                }
+                None
+            }
+        }
+    }
+
+    fn key_up(&mut self, key: VKey) -> Option<Result<HotkeySpec, ShortcutError>> {
+        self.pressed.retain(|pressed| *pressed != key);
+        let trigger = self.trigger();
+        if trigger == Some(key) {
+            return Some(self.commit());
+        }
+        if trigger.is_none() && self.pressed.is_empty() && !self.peak.is_empty() {
+            self.reset();
+            return Some(Err(ShortcutError::ModifierOnly));
+        }
+        None
+    }
+
+    /// The last key of the combination that is not a modifier.
+    fn trigger(&self) -> Option<VKey> {
+        self.peak
+            .iter()
+            .rev()
+            .find(|key| !key.is_modifier_key())
+            .copied()
+    }
+
+    fn commit(&mut self) -> Result<HotkeySpec, ShortcutError> {
+        let trigger = self.trigger();
+        let peak = std::mem::take(&mut self.peak);
+        self.reset();
+        let trigger = trigger.ok_or(ShortcutError::ModifierOnly)?;
+        let modifiers: Vec<VKey> = peak
+            .into_iter()
+            .filter(|key| *key != trigger)
+            .map(generic)
+            .collect();
+
+        let spec = HotkeySpec::new(trigger, modifiers);
+        if let Some(shortcut) = reserved_overlap(&spec, &self.reserved) {
+            return Err(ShortcutError::Reserved(Box::new(shortcut)));
+        }
+        Ok(spec)
+    }
+
+    fn reset(&mut self) {
+        self.pressed.clear();
+        self.peak.clear();
+    }
+}
+
+/// Side specific modifiers are recorded as generic ones, so the shortcut works
+/// with both sides.
+fn generic(key: VKey) -> VKey {
+    match key {
+        VKey::LShift | VKey::RShift => VKey::Shift,
+        VKey::LControl | VKey::RControl => VKey::Control,
+        VKey::LMenu | VKey::RMenu => VKey::Menu,
+        VKey::RWin => VKey::LWin,
+        key => key,
+    }
+}
+
+#[derive(Default)]
+struct SharedOutcome {
+    outcome: Option<Result<HotkeySpec, ShortcutError>>,
+    waker: Option<Waker>,
+}
+
+/// A shortcut being recorded on a background thread, see
+/// [`crate::HotkeyManager::record_shortcut_async`].
+pub struct ShortcutFuture {
+    shared: Arc<Mutex<SharedOutcome>>,
+}
+
+impl ShortcutFuture {
+    /// Runs `record` on a new thread, the future completes with its outcome.
+    pub(crate) fn spawn<F>(record: F) -> Self
+    where
+        F: FnOnce() -> Result<HotkeySpec, ShortcutError> + Send + 'static,
+    {
+        let shared = Arc::new(Mutex::new(SharedOutcome::default()));
+        let thread_shared = shared.clone();
+        std::thread::spawn(move || {
+            let outcome = record();
+            let mut shared = thread_shared.lock().unwrap();
+            shared.outcome = Some(outcome);
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        });
+        ShortcutFuture { shared }
+    }
+}
+
+impl Future for ShortcutFuture {
+    type Output = Result<HotkeySpec, ShortcutError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut shared = self.shared.lock().unwrap();
+        match shared.outcome.take() {
+            Some(outcome) => Poll::Ready(outcome),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::KeyboardState;
+
+    fn down(key: VKey) -> KeyboardInputEvent {
+        KeyboardInputEvent::KeyDown {
+            key,
+            state: KeyboardState::new(),
+        }
+    }
+
+    fn up(key: VKey) -> KeyboardInputEvent {
+        KeyboardInputEvent::KeyUp {
+            key,
+            state: KeyboardState::new(),
+        }
+    }
+
+    /// Feeds the events, checking nothing is returned before the last one.
+    fn record(
+        recorder: &mut ShortcutRecorder,
+        events: &[KeyboardInputEvent],
+    ) -> Option<Result<HotkeySpec, ShortcutError>> {
+        let (last, events) = events.split_last().unwrap();
+        for event in events {
+            assert!(recorder.handle(event).is_none(), "{event:?}");
+        }
+        recorder.handle(last)
+    }
+
+    #[test]
+    fn test_commit_on_trigger_release() {
+        let mut recorder = ShortcutRecorder::new();
+        let outcome = record(
+            &mut recorder,
+            &[
+                down(VKey::LControl),
+                down(VKey::RShift),
+                down(VKey::K),
+                down(VKey::K),
+                up(VKey::RShift),
+                up(VKey::K),
+            ],
+        );
+        assert_eq!(
+            outcome.unwrap().unwrap(),
+            HotkeySpec::new(VKey::K, [VKey::Control, VKey::Shift]),
+            "Peak set, with generic modifiers"
+        );
+        assert!(recorder.keys().is_empty());
+    }
+
+    #[test]
+    fn test_trigger_is_not_a_modifier() {
+        let mut recorder = ShortcutRecorder::new();
+        let outcome = record(
+            &mut recorder,
+            &[
+                down(VKey::F5),
+                down(VKey::LMenu),
+                up(VKey::LMenu),
+                up(VKey::F5),
+            ],
+        );
+        assert_eq!(
+            outcome.unwrap().unwrap(),
+            HotkeySpec::new(VKey::F5, [VKey::Menu])
+        );
+    }
+
+    #[test]
+    fn test_modifier_only() {
+        let mut recorder = ShortcutRecorder::new();
+        let outcome = record(
+            &mut recorder,
+            &[
+                down(VKey::LControl),
+                down(VKey::LShift),
+                up(VKey::LShift),
+                up(VKey::LControl),
+            ],
+        );
+        assert!(matches!(outcome, Some(Err(ShortcutError::ModifierOnly))));
+    }
+
+    #[test]
+    fn test_escape_cancels() {
+        let mut recorder = ShortcutRecorder::new();
+        let outcome = record(&mut recorder, &[down(VKey::LControl), down(VKey::Escape)]);
+        assert!(matches!(outcome, Some(Err(ShortcutError::Cancelled))));
+    }
+
+    #[test]
+    fn test_backspace_clears() {
+        let mut recorder = ShortcutRecorder::new();
+        for event in [down(VKey::LControl), down(VKey::A), up(VKey::LControl)] {
+            assert!(recorder.handle(&event).is_none());
+        }
+        assert_eq!(recorder.keys(), [VKey::LControl, VKey::A]);
+
+        assert!(recorder.handle(&down(VKey::Back)).is_none());
+        assert!(recorder.keys().is_empty());
+        assert!(recorder.handle(&up(VKey::A)).is_none());
+        assert!(recorder.handle(&up(VKey::Back)).is_none());
+
+        let outcome = record(&mut recorder, &[down(VKey::B), up(VKey::B)]);
+        assert_eq!(outcome.unwrap().unwrap(), HotkeySpec::new(VKey::B, []));
+    }
+
+    #[test]
+    fn test_reserved() {
+        let mut recorder = ShortcutRecorder::new();
+        let outcome = record(
+            &mut recorder,
+            &[down(VKey::LWin), down(VKey::L), up(VKey::L)],
+        );
+        assert!(matches!(
+            outcome,
+            Some(Err(ShortcutError::Reserved(shortcut))) if shortcut.trigger_key == VKey::L
+        ));
+
+        let save = HotkeySpec::new(VKey::S, [VKey::Control]);
+        let mut recorder = ShortcutRecorder::new().reserve(save.clone());
+        let outcome = record(
+            &mut recorder,
+            &[down(VKey::RControl), down(VKey::S), up(VKey::S)],
+        );
+        assert!(matches!(
+            outcome,
+            Some(Err(ShortcutError::Reserved(shortcut))) if *shortcut == save
+        ));
+    }
+}
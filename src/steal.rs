@@ -0,0 +1,143 @@
+//! Options of the keyboard stealing mode, see [`crate::HotkeyManager::steal_keyboard_with`].
+
+use std::time::Duration;
+
+use crate::hotkey::HotkeyId;
+use crate::state::KeyboardState;
+use crate::VKey;
+
+/// Why the keyboard was freed from the stealing mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FreeReason {
+    /// An exit combination was pressed
+    ExitKey,
+    /// The stealing timeout elapsed
+    Timeout,
+    /// The keyboard was freed with [`crate::HotkeyManager::free_keyboard`]
+    Programmatic,
+}
+
+/// Configures the keyboard stealing mode.
+///
+/// By default `ESCAPE` frees the keyboard, there is no timeout, key releases are
+/// not blocked and no hotkey is exempt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StealOptions {
+    /// combinations freeing the keyboard, the last key of each being the trigger
+    pub exit_combos: Vec<Vec<VKey>>,
+    /// time after which the keyboard is freed on its own
+    pub timeout: Option<Duration>,
+    /// blocks the releases of the keys pressed while stealing too
+    pub block_key_ups: bool,
+    /// hotkeys that keep working while the keyboard is stolen
+    pub exempt: Vec<HotkeyId>,
+}
+
+impl Default for StealOptions {
+    fn default() -> Self {
+        Self {
+            exit_combos: vec![vec![VKey::Escape]],
+            timeout: None,
+            block_key_ups: false,
+            exempt: Vec::new(),
+        }
+    }
+}
+
+impl StealOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the exit combinations by `keys`, the last key being the trigger.
+    pub fn exit_combo<T: AsRef<[VKey]>>(mut self, keys: T) -> Self {
+        self.exit_combos = vec![keys.as_ref().to_vec()];
+        self
+    }
+
+    /// Adds an exit combination, the last key being the trigger.
+    pub fn or_exit_combo<T: AsRef<[VKey]>>(mut self, keys: T) -> Self {
+        self.exit_combos.push(keys.as_ref().to_vec());
+        self
+    }
+
+    /// Removes the exit combinations, the keyboard is only freed by the timeout or
+    /// by [`crate::HotkeyManager::free_keyboard`].
+    pub fn no_exit_combo(mut self) -> Self {
+        self.exit_combos.clear();
+        self
+    }
+
+    /// Frees the keyboard on its own after `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Blocks the releases of the keys pressed while stealing too, the exempt hotkeys
+    /// triggered on release still run.
+    ///
+    /// Releases of keys pressed before the stealing started go through, as other
+    /// applications saw their presses, e.g. the keys of the hotkey starting the stealing.
+    pub fn block_key_ups(mut self) -> Self {
+        self.block_key_ups = true;
+        self
+    }
+
+    /// Keeps the hotkey `id` working while the keyboard is stolen.
+    pub fn exempt(mut self, id: HotkeyId) -> Self {
+        self.exempt.push(id);
+        self
+    }
+
+    /// Checks if the press of `key` completes an exit combination.
+    pub(crate) fn is_exit(&self, key: VKey, state: &KeyboardState) -> bool {
+        self.exit_combos.iter().any(|combo| {
+            combo.last().is_some_and(|trigger| trigger.matches(&key))
+                && combo.iter().all(|combo_key| state.is_down(*combo_key))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(keys: &[VKey]) -> KeyboardState {
+        let mut state = KeyboardState::new();
+        for key in keys {
+            state.keydown(*key);
+        }
+        state
+    }
+
+    #[test]
+    fn test_default_exit_key() {
+        let options = StealOptions::default();
+        assert!(options.is_exit(VKey::Escape, &state(&[VKey::Escape])));
+        assert!(!options.is_exit(VKey::A, &state(&[VKey::A])));
+    }
+
+    #[test]
+    fn test_exit_combos() {
+        let options = StealOptions::new()
+            .exit_combo([VKey::Control, VKey::Q])
+            .or_exit_combo([VKey::F12]);
+        assert!(options.is_exit(VKey::Q, &state(&[VKey::RControl, VKey::Q])));
+        assert!(options.is_exit(VKey::F12, &state(&[VKey::F12])));
+        assert!(!options.is_exit(VKey::Q, &state(&[VKey::Q])));
+        assert!(!options.is_exit(VKey::Escape, &state(&[VKey::Escape])));
+        assert!(
+            !options.is_exit(VKey::RControl, &state(&[VKey::Q, VKey::RControl])),
+            "The last key is the trigger"
+        );
+    }
+
+    #[test]
+    fn test_no_exit_combo() {
+        let options = StealOptions::new().no_exit_combo();
+        assert!(!options.is_exit(VKey::Escape, &state(&[VKey::Escape])));
+    }
+}
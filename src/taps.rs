@@ -0,0 +1,253 @@
+//! Tap counting for multi-tap hotkeys (double tap, triple tap...).
+//!
+//! The [`TapTracker`] only knows about tap counts and time, the manager feeds it
+//! the tap counts of the hotkeys matching an event and maps the decision back to
+//! the hotkeys.
+
+use std::time::{Duration, Instant};
+
+use crate::VKey;
+
+/// Decision taken after registering a tap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TapDecision {
+    /// The hotkey with this tap count should fire right away.
+    Fire(u8),
+    /// The hotkey with this tap count matches, but a hotkey with more taps
+    /// could still match, so it should fire at `deadline` unless another tap
+    /// arrives first.
+    Defer { taps: u8, deadline: Instant },
+    /// No hotkey matches yet, but a hotkey with more taps could still match.
+    Wait,
+    /// No hotkey matches this tap count.
+    Miss,
+}
+
+#[derive(Debug)]
+struct TapRun {
+    combo: u64,
+    key: VKey,
+    count: u8,
+    last: Instant,
+}
+
+/// Tracks consecutive taps of the same key combination.
+#[derive(Debug, Default)]
+pub(crate) struct TapTracker {
+    run: Option<TapRun>,
+}
+
+impl TapTracker {
+    /// Registers a tap of `combo` and decides what to do with it.
+    ///
+    /// `taps` are the tap counts of the hotkeys matching the tap, and `window` is
+    /// the maximum time allowed between two consecutive taps.
+    pub fn tap(
+        &mut self,
+        combo: u64,
+        key: VKey,
+        taps: &[u8],
+        window: Duration,
+        now: Instant,
+    ) -> TapDecision {
+        let count = match &self.run {
+            Some(run)
+                if run.combo == combo && now.saturating_duration_since(run.last) <= window =>
+            {
+                run.count.saturating_add(1)
+            }
+            _ => 1,
+        };
+
+        let longest = taps.iter().copied().max().unwrap_or(0);
+        let decision = if taps.contains(&count) {
+            if count < longest {
+                TapDecision::Defer {
+                    taps: count,
+                    deadline: now + window,
+                }
+            } else {
+                TapDecision::Fire(count)
+            }
+        } else if count < longest {
+            TapDecision::Wait
+        } else {
+            TapDecision::Miss
+        };
+
+        self.run = match decision {
+            TapDecision::Fire(_) | TapDecision::Miss => None,
+            TapDecision::Defer { .. } | TapDecision::Wait => Some(TapRun {
+                combo,
+                key,
+                count,
+                last: now,
+            }),
+        };
+        decision
+    }
+
+    /// Ends the current run if it belongs to `combo`, called once a deferred tap fired.
+    pub fn finish(&mut self, combo: u64) {
+        if self.run.as_ref().is_some_and(|run| run.combo == combo) {
+            self.run = None;
+        }
+    }
+
+    /// Breaks the current run when another non modifier key is pressed.
+    pub fn interrupt(&mut self, key: VKey) {
+        if key.is_modifier_key() {
+            return;
+        }
+        if self.run.as_ref().is_some_and(|run| run.key != key) {
+            self.run = None;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.run = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    const WINDOW: Duration = Duration::from_millis(300);
+
+    #[test]
+    fn test_double_tap_fires_on_second_tap() {
+        let clock = ManualClock::new();
+        let mut tracker = TapTracker::default();
+
+        assert_eq!(
+            tracker.tap(1, VKey::Shift, &[2], WINDOW, clock.now()),
+            TapDecision::Wait
+        );
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(
+            tracker.tap(1, VKey::Shift, &[2], WINDOW, clock.now()),
+            TapDecision::Fire(2)
+        );
+    }
+
+    #[test]
+    fn test_double_tap_outside_window_restarts() {
+        let clock = ManualClock::new();
+        let mut tracker = TapTracker::default();
+
+        tracker.tap(1, VKey::Shift, &[2], WINDOW, clock.now());
+        clock.advance(Duration::from_millis(400));
+        assert_eq!(
+            tracker.tap(1, VKey::Shift, &[2], WINDOW, clock.now()),
+            TapDecision::Wait,
+            "A tap after the window should start a new run"
+        );
+        clock.advance(Duration::from_millis(200));
+        assert_eq!(
+            tracker.tap(1, VKey::Shift, &[2], WINDOW, clock.now()),
+            TapDecision::Fire(2)
+        );
+    }
+
+    #[test]
+    fn test_single_tap_is_deferred_when_double_tap_exists() {
+        let clock = ManualClock::new();
+        let mut tracker = TapTracker::default();
+
+        let start = clock.now();
+        assert_eq!(
+            tracker.tap(1, VKey::Shift, &[1, 2], WINDOW, start),
+            TapDecision::Defer {
+                taps: 1,
+                deadline: start + WINDOW
+            },
+            "Single tap should wait for a possible second tap"
+        );
+
+        clock.advance(Duration::from_millis(150));
+        assert_eq!(
+            tracker.tap(1, VKey::Shift, &[1, 2], WINDOW, clock.now()),
+            TapDecision::Fire(2),
+            "Second tap should resolve to the double tap"
+        );
+    }
+
+    #[test]
+    fn test_single_tap_finished_after_deferred_fire() {
+        let clock = ManualClock::new();
+        let mut tracker = TapTracker::default();
+
+        tracker.tap(1, VKey::Shift, &[1, 2], WINDOW, clock.now());
+        clock.advance(WINDOW);
+        tracker.finish(1);
+
+        clock.advance(Duration::from_millis(10));
+        assert!(
+            matches!(
+                tracker.tap(1, VKey::Shift, &[1, 2], WINDOW, clock.now()),
+                TapDecision::Defer { taps: 1, .. }
+            ),
+            "A tap after a resolved single tap should start a new run"
+        );
+    }
+
+    #[test]
+    fn test_triple_tap_defers_double_tap() {
+        let clock = ManualClock::new();
+        let mut tracker = TapTracker::default();
+
+        let taps = [2, 3];
+        assert_eq!(
+            tracker.tap(1, VKey::A, &taps, WINDOW, clock.now()),
+            TapDecision::Wait
+        );
+        clock.advance(Duration::from_millis(100));
+        assert!(matches!(
+            tracker.tap(1, VKey::A, &taps, WINDOW, clock.now()),
+            TapDecision::Defer { taps: 2, .. }
+        ));
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(
+            tracker.tap(1, VKey::A, &taps, WINDOW, clock.now()),
+            TapDecision::Fire(3)
+        );
+    }
+
+    #[test]
+    fn test_other_key_interrupts_run() {
+        let clock = ManualClock::new();
+        let mut tracker = TapTracker::default();
+
+        tracker.tap(1, VKey::A, &[2], WINDOW, clock.now());
+        tracker.interrupt(VKey::B);
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(
+            tracker.tap(1, VKey::A, &[2], WINDOW, clock.now()),
+            TapDecision::Wait,
+            "Pressing another key between taps should break the run"
+        );
+
+        // modifiers and the tapped key itself don't break the run
+        tracker.interrupt(VKey::LControl);
+        tracker.interrupt(VKey::A);
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(
+            tracker.tap(1, VKey::A, &[2], WINDOW, clock.now()),
+            TapDecision::Fire(2)
+        );
+    }
+
+    #[test]
+    fn test_different_combo_restarts_run() {
+        let clock = ManualClock::new();
+        let mut tracker = TapTracker::default();
+
+        tracker.tap(1, VKey::A, &[2], WINDOW, clock.now());
+        assert_eq!(
+            tracker.tap(2, VKey::A, &[2], WINDOW, clock.now()),
+            TapDecision::Wait
+        );
+    }
+}
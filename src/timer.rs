@@ -0,0 +1,70 @@
+//! Deadlines handled by the event loop thread.
+//!
+//! Some triggers need to act without a new keyboard event (e.g. a single tap
+//! that is resolved once the double tap window has elapsed). Those schedule a
+//! [`TimerEvent`] here, and the event loop wakes up on the nearest deadline.
+
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Instant;
+
+use crate::client_executor::HotkeyCall;
+use crate::hotkey::HotkeyId;
+use crate::usage::Usage;
+
+pub(crate) static TIMERS: LazyLock<Mutex<TimerQueue>> =
+    LazyLock::new(|| Mutex::new(TimerQueue::default()));
+
+/// Enum representing the actions that can be scheduled on the event loop.
+pub(crate) enum TimerEvent {
+    /// The tap window of a deferred tap hotkey elapsed without a new tap.
+    TapWindowElapsed { combo: u64, call: HotkeyCall },
+    /// A hold duration of the pending press may have been reached.
+    HoldElapsed,
+    /// A hotkey ran out of uses or its lifetime elapsed, it has to be unregistered.
+    HotkeyExpired { id: HotkeyId, usage: Arc<Usage> },
+    /// A hotkey held down may have to fire again.
+    HeldRepeatDue,
+    /// A timed pause of the hotkeys may have elapsed.
+    PauseElapsed,
+    /// The keyboard has been stolen for the timeout of the stealing mode.
+    StealTimeout,
+}
+
+#[derive(Default)]
+pub(crate) struct TimerQueue {
+    entries: Vec<(Instant, TimerEvent)>,
+}
+
+impl TimerQueue {
+    pub fn schedule(&mut self, deadline: Instant, event: TimerEvent) {
+        self.entries.push((deadline, event));
+    }
+
+    /// Removes every scheduled event matching the predicate.
+    pub fn cancel<F: Fn(&TimerEvent) -> bool>(&mut self, predicate: F) {
+        self.entries.retain(|(_, event)| !predicate(event));
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.entries.iter().map(|(deadline, _)| *deadline).min()
+    }
+
+    /// Removes and returns the events whose deadline is reached, oldest first.
+    pub fn take_due(&mut self, now: Instant) -> Vec<TimerEvent> {
+        let mut due = Vec::new();
+        let mut i = 0;
+        while i < self.entries.len() {
+            if self.entries[i].0 <= now {
+                due.push(self.entries.remove(i));
+            } else {
+                i += 1;
+            }
+        }
+        due.sort_by_key(|(deadline, _)| *deadline);
+        due.into_iter().map(|(_, event)| event).collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -0,0 +1,108 @@
+//! Lifetime of the hotkeys limited to a number of uses or to a duration,
+//! see [`Hotkey::times`] and [`Hotkey::expires_after`].
+//!
+//! The usage is shared between the registered hotkey and the calls prepared from it,
+//! so a use is consumed when the callback actually runs, whatever the trigger.
+//!
+//! [`Hotkey::times`]: crate::Hotkey::times
+//! [`Hotkey::expires_after`]: crate::Hotkey::expires_after
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Instant;
+
+#[derive(Debug, Default)]
+pub(crate) struct Usage {
+    max_uses: Option<u32>,
+    expires_at: Option<Instant>,
+    uses: AtomicU32,
+}
+
+impl Usage {
+    pub fn new(max_uses: Option<u32>, expires_at: Option<Instant>) -> Self {
+        Self {
+            max_uses,
+            expires_at,
+            uses: AtomicU32::new(0),
+        }
+    }
+
+    pub fn expires_at(&self) -> Option<Instant> {
+        self.expires_at
+    }
+
+    /// Checks whether the hotkey still has uses left and is not expired.
+    pub fn is_active(&self, now: Instant) -> bool {
+        let uses = self.uses.load(Ordering::SeqCst);
+        self.expires_at.is_none_or(|expires_at| now < expires_at)
+            && self.max_uses.is_none_or(|max| uses < max)
+    }
+
+    /// Consumes a use, returns `None` if the hotkey is no longer active, or whether
+    /// that was its last use.
+    pub fn try_use(&self, now: Instant) -> Option<bool> {
+        if self.expires_at.is_some_and(|expires_at| now >= expires_at) {
+            return None;
+        }
+        let Some(max) = self.max_uses else {
+            self.uses.fetch_add(1, Ordering::SeqCst);
+            return Some(false);
+        };
+        let previous = self
+            .uses
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |uses| {
+                (uses < max).then_some(uses + 1)
+            })
+            .ok()?;
+        Some(previous + 1 == max)
+    }
+
+    pub fn is_unused(&self) -> bool {
+        self.uses.load(Ordering::SeqCst) == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+    use std::time::Duration;
+
+    #[test]
+    fn test_unlimited() {
+        let clock = ManualClock::new();
+        let usage = Usage::default();
+        for _ in 0..10 {
+            assert_eq!(usage.try_use(clock.now()), Some(false));
+        }
+        assert!(usage.is_active(clock.now()));
+    }
+
+    #[test]
+    fn test_limited_uses() {
+        let clock = ManualClock::new();
+        let usage = Usage::new(Some(2), None);
+
+        assert!(usage.is_unused());
+        assert_eq!(usage.try_use(clock.now()), Some(false));
+        assert!(!usage.is_unused());
+        assert_eq!(
+            usage.try_use(clock.now()),
+            Some(true),
+            "Second use is the last"
+        );
+        assert!(!usage.is_active(clock.now()));
+        assert_eq!(usage.try_use(clock.now()), None);
+    }
+
+    #[test]
+    fn test_expiration() {
+        let clock = ManualClock::new();
+        let usage = Usage::new(None, Some(clock.now() + Duration::from_secs(5)));
+
+        assert!(usage.is_active(clock.now()));
+        clock.advance(Duration::from_secs(5));
+        assert!(!usage.is_active(clock.now()));
+        assert_eq!(usage.try_use(clock.now()), None);
+        assert!(usage.is_unused());
+    }
+}
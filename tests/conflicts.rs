@@ -0,0 +1,174 @@
+//! Tests for the hotkey conflict analysis.
+
+use std::time::Duration;
+
+use win_hotkeys::{
+    analyze_conflicts, analyze_conflicts_including_disabled, Conflict, HotkeySpec, MatchPolicy,
+    ModifierMatch, TriggerBehavior, TriggerTiming, VKey,
+};
+
+fn ctrl_a() -> HotkeySpec {
+    HotkeySpec::new(VKey::A, [VKey::Control])
+}
+
+#[test]
+fn test_no_conflicts() {
+    let specs = vec![
+        ctrl_a(),
+        HotkeySpec::new(VKey::B, [VKey::Control]),
+        HotkeySpec::new(VKey::A, [VKey::Control, VKey::Shift]),
+    ];
+    assert!(analyze_conflicts(&specs, MatchPolicy::FirstMatch).is_empty());
+}
+
+#[test]
+fn test_duplicate() {
+    let mut passing = ctrl_a();
+    passing.behaviour = TriggerBehavior::PassThrough;
+
+    let conflicts = analyze_conflicts(&[ctrl_a(), passing.clone()], MatchPolicy::FirstMatch);
+    assert_eq!(
+        conflicts,
+        vec![Conflict::Duplicate {
+            hotkey: ctrl_a(),
+            other: passing,
+        }]
+    );
+}
+
+#[test]
+fn test_strict_sequence_shadows_relaxed() {
+    let mut strict = ctrl_a();
+    strict.strict_sequence = true;
+
+    let conflicts = analyze_conflicts(&[ctrl_a(), strict.clone()], MatchPolicy::FirstMatch);
+    assert_eq!(
+        conflicts,
+        vec![Conflict::Shadowed {
+            hotkey: ctrl_a(),
+            by: strict.clone(),
+        }]
+    );
+
+    assert!(
+        analyze_conflicts(&[ctrl_a(), strict], MatchPolicy::AllMatches).is_empty(),
+        "Every matching hotkey runs, nothing is shadowed"
+    );
+}
+
+#[test]
+fn test_registration_order_shadows_ties() {
+    let mut left = HotkeySpec::new(VKey::A, [VKey::LControl]);
+    left.bypass_pause = true;
+
+    let conflicts = analyze_conflicts(&[ctrl_a(), left.clone()], MatchPolicy::FirstMatch);
+    assert_eq!(
+        conflicts,
+        vec![Conflict::Shadowed {
+            hotkey: left,
+            by: ctrl_a(),
+        }]
+    );
+}
+
+#[test]
+fn test_priority_shadows() {
+    let mut relaxed = ctrl_a();
+    relaxed.modifier_match = ModifierMatch::AtLeast;
+    relaxed.priority = 1;
+    let ctrl_shift_a = HotkeySpec::new(VKey::A, [VKey::Control, VKey::Shift]);
+
+    let conflicts = analyze_conflicts(
+        &[ctrl_shift_a.clone(), relaxed.clone()],
+        MatchPolicy::FirstMatch,
+    );
+    assert_eq!(
+        conflicts,
+        vec![Conflict::Shadowed {
+            hotkey: ctrl_shift_a,
+            by: relaxed,
+        }]
+    );
+}
+
+#[test]
+fn test_hold_behind_key_down_is_unreachable() {
+    let mut hold = ctrl_a();
+    hold.trigger_timing = TriggerTiming::OnHold(Duration::from_millis(500));
+
+    let conflicts = analyze_conflicts(&[hold.clone(), ctrl_a()], MatchPolicy::FirstMatch);
+    assert_eq!(
+        conflicts,
+        vec![Conflict::Unreachable {
+            hotkey: hold,
+            by: ctrl_a(),
+        }]
+    );
+}
+
+#[test]
+fn test_key_up_is_reachable() {
+    let mut key_up = ctrl_a();
+    key_up.trigger_timing = TriggerTiming::OnKeyUp;
+
+    // the key up event still reaches the hotkeys when the key down is blocked
+    assert!(analyze_conflicts(&[ctrl_a(), key_up], MatchPolicy::FirstMatch).is_empty());
+}
+
+#[test]
+fn test_reserved_shortcuts() {
+    let lock = HotkeySpec::new(VKey::L, [VKey::LWin]);
+    let conflicts = analyze_conflicts(std::slice::from_ref(&lock), MatchPolicy::FirstMatch);
+    assert!(matches!(
+        &conflicts[..],
+        [Conflict::ReservedShortcut { hotkey, shortcut }]
+            if *hotkey == lock && shortcut.trigger_key == VKey::L
+    ));
+
+    let mut relaxed_delete = HotkeySpec::new(VKey::Delete, [VKey::Control]);
+    relaxed_delete.modifier_match = ModifierMatch::AtLeast;
+    let conflicts = analyze_conflicts(&[relaxed_delete], MatchPolicy::FirstMatch);
+    assert!(matches!(
+        &conflicts[..],
+        [Conflict::ReservedShortcut { shortcut, .. }] if shortcut.trigger_key == VKey::Delete
+    ));
+
+    let win_shift_l = HotkeySpec::new(VKey::L, [VKey::LWin, VKey::Shift]);
+    assert!(analyze_conflicts(&[win_shift_l], MatchPolicy::FirstMatch).is_empty());
+}
+
+#[test]
+fn test_disabled_hotkeys_are_ignored_unless_asked() {
+    let mut disabled = ctrl_a();
+    disabled.enabled = false;
+    let specs = [ctrl_a(), disabled.clone()];
+
+    assert!(analyze_conflicts(&specs, MatchPolicy::FirstMatch).is_empty());
+    assert_eq!(
+        analyze_conflicts_including_disabled(&specs, MatchPolicy::FirstMatch),
+        vec![Conflict::Duplicate {
+            hotkey: ctrl_a(),
+            other: disabled,
+        }]
+    );
+}
+
+#[test]
+fn test_layers_conflict_within_a_layer() {
+    let mut nav = ctrl_a();
+    nav.layer = Some("nav".to_owned());
+    let mut symbols = ctrl_a();
+    symbols.layer = Some("symbols".to_owned());
+
+    let specs = [ctrl_a(), nav.clone(), symbols];
+    assert!(analyze_conflicts(&specs, MatchPolicy::FirstMatch).is_empty());
+
+    let specs = [nav.clone(), nav.clone()];
+    assert_eq!(
+        analyze_conflicts(&specs, MatchPolicy::FirstMatch),
+        vec![Conflict::Duplicate {
+            hotkey: nav.clone(),
+            other: nav,
+        }]
+    );
+}
@@ -0,0 +1,59 @@
+//! Tests for the hotkey groups of the manager.
+
+use win_hotkeys::{Hotkey, HotkeyManager, VKey};
+
+fn hotkey(key: VKey) -> Hotkey {
+    Hotkey::new(key, [VKey::Control, VKey::Menu], || {})
+}
+
+#[test]
+fn test_group_operations() {
+    let hkm = HotkeyManager::current();
+    let media = hkm.create_group("media");
+    let play = media.register_hotkey(hotkey(VKey::F1)).unwrap();
+    let next = media.register_hotkey(hotkey(VKey::F2)).unwrap();
+    let global = hkm.register_hotkey(hotkey(VKey::F3)).unwrap();
+
+    let listed: Vec<VKey> = media
+        .list_hotkeys()
+        .iter()
+        .map(|spec| spec.trigger_key)
+        .collect();
+    assert_eq!(listed, [VKey::F1, VKey::F2]);
+    assert!(media
+        .list_hotkeys()
+        .iter()
+        .all(|spec| spec.group == Some(media.id())));
+
+    media.pause();
+    assert!(media.is_paused());
+    assert!(hkm.is_group_paused(media.id()));
+    media.resume();
+    assert!(!media.is_paused());
+
+    media.set_enabled(false);
+    assert!(!hkm.is_enabled(play).unwrap());
+    assert!(!hkm.is_enabled(next).unwrap());
+    assert!(hkm.is_enabled(global).unwrap());
+
+    media.unregister_all();
+    assert!(media.list_hotkeys().is_empty());
+    assert!(hkm.is_enabled(global).is_ok(), "Other hotkeys are kept");
+    hkm.unregister_hotkey(global).unwrap();
+}
+
+#[test]
+fn test_groups_are_isolated() {
+    let hkm = HotkeyManager::current();
+    let first = hkm.create_group("plugin");
+    let second = hkm.create_group("plugin");
+    assert_ne!(first.id(), second.id());
+
+    let id = first.register_hotkey(hotkey(VKey::F5)).unwrap();
+    assert!(second.unregister_hotkey(id).is_err());
+    second.unregister_all();
+    assert_eq!(first.list_hotkeys().len(), 1);
+
+    first.unregister_hotkey(id).unwrap();
+    assert!(first.list_hotkeys().is_empty());
+}
@@ -0,0 +1,65 @@
+//! Tests for hotkey callbacks receiving the `HotkeyEvent` context.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use win_hotkeys::events::HotkeyEvent;
+use win_hotkeys::{Hotkey, HotkeyId, TriggerTiming, VKey};
+
+#[test]
+fn test_context_callback_receives_hotkey_event() {
+    let received: Arc<Mutex<Option<HotkeyEvent>>> = Arc::default();
+    let sink = received.clone();
+
+    let hotkey = Hotkey::with_context(VKey::A, [VKey::Control], move |event| {
+        *sink.lock().unwrap() = Some(event.clone());
+    })
+    .trigger_timing(TriggerTiming::OnKeyUp);
+    hotkey.execute();
+
+    let event = received
+        .lock()
+        .unwrap()
+        .take()
+        .expect("callback should run");
+    assert_eq!(event.id, HotkeyId::default(), "Not registered, no id");
+    assert_eq!(event.trigger_key, VKey::A);
+    assert_eq!(event.timing, TriggerTiming::OnKeyUp);
+    assert_eq!(event.repeat, 0);
+    assert!(event.state.is_control_pressed());
+}
+
+#[test]
+fn test_shared_handler_can_tell_hotkeys_apart() {
+    let fired: Arc<Mutex<Vec<VKey>>> = Arc::default();
+    let handler = {
+        let fired = fired.clone();
+        move |event: &HotkeyEvent| fired.lock().unwrap().push(event.trigger_key)
+    };
+
+    let first = Hotkey::with_context(VKey::Digit1, [VKey::LWin], handler.clone());
+    let second = Hotkey::with_context(VKey::Digit2, [VKey::LWin], handler);
+    second.execute();
+    first.execute();
+
+    assert_eq!(*fired.lock().unwrap(), vec![VKey::Digit2, VKey::Digit1]);
+}
+
+#[test]
+fn test_plain_callbacks_still_work() {
+    let count = Arc::new(AtomicUsize::new(0));
+
+    let counter = count.clone();
+    let hotkey = Hotkey::new(VKey::B, [VKey::Shift], move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+    hotkey.execute();
+
+    let counter = count.clone();
+    let hotkey = Hotkey::from_keys([VKey::Shift, VKey::C]).action(move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+    hotkey.execute();
+
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,32 @@
+//! Tests for the pause reasons of the manager.
+
+use std::time::Duration;
+
+use win_hotkeys::HotkeysPauseHandler;
+
+#[test]
+fn test_pause_reasons() {
+    let pause = HotkeysPauseHandler::current();
+    assert!(!pause.is_paused());
+
+    let menu = pause.pause("menu");
+    let dialog = pause.pause("dialog");
+    assert!(pause.is_paused());
+    assert_eq!(pause.reasons(), ["menu", "dialog"]);
+
+    drop(menu);
+    assert!(pause.is_paused(), "The dialog still pauses the hotkeys");
+    assert_eq!(pause.reasons(), ["dialog"]);
+
+    pause.set(true);
+    drop(dialog);
+    assert!(pause.is_paused(), "Manual pause");
+    assert!(pause.reasons().is_empty());
+
+    pause.toggle();
+    assert!(!pause.is_paused());
+
+    pause.pause_for(Duration::from_secs(60), "presentation");
+    assert!(pause.is_paused());
+    assert_eq!(pause.reasons(), ["presentation"]);
+}
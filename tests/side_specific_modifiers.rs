@@ -0,0 +1,189 @@
+//! Tests for side specific modifiers (LControl, RMenu...) used as hotkey modifiers.
+//!
+//! Side specific modifiers only match the declared side, while the generic
+//! `Control`, `Shift` and `Menu` keys (and both windows keys) match either side.
+
+use win_hotkeys::state::KeyboardState;
+use win_hotkeys::VKey;
+use win_hotkeys::{Hotkey, ModifierMatch, TriggerTiming};
+
+#[test]
+fn test_left_control_matches_left_side() {
+    let hotkey = Hotkey::new(VKey::Up, [VKey::LControl], || {});
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::LControl);
+    state.keydown(VKey::Up);
+
+    assert!(hotkey.is_trigger_state(&VKey::Up, &state));
+}
+
+#[test]
+fn test_left_control_does_not_match_right_side() {
+    let hotkey = Hotkey::new(VKey::Up, [VKey::LControl], || {});
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::RControl);
+    state.keydown(VKey::Up);
+
+    assert!(
+        !hotkey.is_trigger_state(&VKey::Up, &state),
+        "LCtrl + Up should NOT trigger when RCtrl is held"
+    );
+}
+
+#[test]
+fn test_left_and_right_bindings_are_separate() {
+    let left = Hotkey::new(VKey::Up, [VKey::LControl], || {});
+    let right = Hotkey::new(VKey::Up, [VKey::RControl], || {});
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::RControl);
+    state.keydown(VKey::Up);
+
+    assert!(!left.is_trigger_state(&VKey::Up, &state));
+    assert!(right.is_trigger_state(&VKey::Up, &state));
+    assert_ne!(left, right);
+}
+
+#[test]
+fn test_generic_control_matches_either_side() {
+    let hotkey = Hotkey::new(VKey::Up, [VKey::Control], || {});
+
+    for side in [VKey::LControl, VKey::RControl] {
+        let mut state = KeyboardState::new();
+        state.keydown(side);
+        state.keydown(VKey::Up);
+
+        assert!(
+            hotkey.is_trigger_state(&VKey::Up, &state),
+            "Ctrl + Up should trigger with {side:?}"
+        );
+    }
+}
+
+#[test]
+fn test_generic_control_with_both_sides_held() {
+    let hotkey = Hotkey::new(VKey::Up, [VKey::Control], || {});
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::LControl);
+    state.keydown(VKey::RControl);
+    state.keydown(VKey::Up);
+
+    assert!(hotkey.is_trigger_state(&VKey::Up, &state));
+}
+
+#[test]
+fn test_exact_side_rejects_other_side_also_held() {
+    let hotkey = Hotkey::new(VKey::Up, [VKey::LControl], || {});
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::LControl);
+    state.keydown(VKey::RControl);
+    state.keydown(VKey::Up);
+
+    assert!(
+        !hotkey.is_trigger_state(&VKey::Up, &state),
+        "Exact LCtrl + Up should NOT trigger when RCtrl is held too"
+    );
+}
+
+#[test]
+fn test_at_least_side_allows_other_side_held() {
+    let hotkey =
+        Hotkey::new(VKey::Up, [VKey::LControl], || {}).modifier_match(ModifierMatch::AtLeast);
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::LControl);
+    state.keydown(VKey::RControl);
+    state.keydown(VKey::Up);
+
+    assert!(hotkey.is_trigger_state(&VKey::Up, &state));
+}
+
+#[test]
+fn test_right_alt_only() {
+    let hotkey = Hotkey::new(VKey::E, [VKey::RMenu], || {});
+
+    let mut right = KeyboardState::new();
+    right.keydown(VKey::RMenu);
+    right.keydown(VKey::E);
+
+    let mut left = KeyboardState::new();
+    left.keydown(VKey::LMenu);
+    left.keydown(VKey::E);
+
+    assert!(hotkey.is_trigger_state(&VKey::E, &right));
+    assert!(!hotkey.is_trigger_state(&VKey::E, &left));
+}
+
+#[test]
+fn test_mixed_side_and_generic_modifiers() {
+    // LShift + (either) Control + K
+    let hotkey = Hotkey::new(VKey::K, [VKey::LShift, VKey::Control], || {});
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::RControl);
+    state.keydown(VKey::LShift);
+    state.keydown(VKey::K);
+    assert!(hotkey.is_trigger_state(&VKey::K, &state));
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::RControl);
+    state.keydown(VKey::RShift);
+    state.keydown(VKey::K);
+    assert!(
+        !hotkey.is_trigger_state(&VKey::K, &state),
+        "RShift should not satisfy LShift"
+    );
+}
+
+#[test]
+fn test_windows_keys_are_not_side_specific() {
+    let hotkey = Hotkey::new(VKey::D, [VKey::LWin], || {});
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::RWin);
+    state.keydown(VKey::D);
+
+    assert!(hotkey.is_trigger_state(&VKey::D, &state));
+}
+
+#[test]
+fn test_side_specific_on_key_up() {
+    let hotkey =
+        Hotkey::new(VKey::Up, [VKey::RControl], || {}).trigger_timing(TriggerTiming::OnKeyUp);
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::LControl);
+    state.keydown(VKey::Up);
+    state.keyup(VKey::Up);
+    assert!(!hotkey.is_trigger_state(&VKey::Up, &state));
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::RControl);
+    state.keydown(VKey::Up);
+    state.keyup(VKey::Up);
+    assert!(hotkey.is_trigger_state(&VKey::Up, &state));
+}
+
+#[test]
+fn test_side_specific_with_strict_sequence() {
+    let hotkey = Hotkey::new(VKey::A, [VKey::LControl, VKey::LShift], || {}).strict_sequence();
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::LControl);
+    state.keydown(VKey::LShift);
+    state.keydown(VKey::A);
+    assert!(hotkey.is_trigger_state(&VKey::A, &state));
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::LShift);
+    state.keydown(VKey::LControl);
+    state.keydown(VKey::A);
+    assert!(
+        !hotkey.is_trigger_state(&VKey::A, &state),
+        "The sequence order is still enforced"
+    );
+}
@@ -9,10 +9,11 @@
 //! 5. Sequence reset after all keys released
 //! 6. OnKeyUp timing with strict sequences
 //! 7. Complex multi-modifier sequences
+//! 8. Relaxed modifier matching modes (AtLeast, AnyOf) with and without strict sequences
 
 use win_hotkeys::state::KeyboardState;
 use win_hotkeys::VKey;
-use win_hotkeys::{Hotkey, TriggerTiming};
+use win_hotkeys::{Hotkey, ModifierMatch, TriggerTiming};
 
 // ============================================================================
 // BASIC STRICT SEQUENCE TESTS - OnKeyDown
@@ -793,3 +794,172 @@ fn test_strict_sequence_long_hold_on_key_up() {
         "Should trigger even after very long hold (many repeated keydown events)"
     );
 }
+
+// ============================================================================
+// MODIFIER MATCH MODES
+// ============================================================================
+
+#[test]
+fn test_exact_modifiers_reject_extra_modifier() {
+    // Hotkey: Win + A (default exact match)
+    let hotkey = Hotkey::new(VKey::A, [VKey::LWin], || {});
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::LWin);
+    state.keydown(VKey::LMenu);
+    state.keydown(VKey::A);
+
+    assert!(
+        !hotkey.is_trigger_state(&VKey::A, &state),
+        "Exact match should NOT trigger when Alt is also held"
+    );
+}
+
+#[test]
+fn test_at_least_modifiers_allow_extra_modifier() {
+    // Hotkey: Control + A, tolerating other modifiers
+    let hotkey =
+        Hotkey::new(VKey::A, [VKey::Control], || {}).modifier_match(ModifierMatch::AtLeast);
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::LControl);
+    state.keydown(VKey::LShift);
+    state.keydown(VKey::A);
+
+    assert!(
+        hotkey.is_trigger_state(&VKey::A, &state),
+        "AtLeast should trigger when Shift is held in addition to Control"
+    );
+}
+
+#[test]
+fn test_at_least_modifiers_require_all_modifiers() {
+    // Hotkey: Control + Shift + A, tolerating other modifiers
+    let hotkey = Hotkey::new(VKey::A, [VKey::Control, VKey::Shift], || {})
+        .modifier_match(ModifierMatch::AtLeast);
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::LControl);
+    state.keydown(VKey::LMenu);
+    state.keydown(VKey::A);
+
+    assert!(
+        !hotkey.is_trigger_state(&VKey::A, &state),
+        "AtLeast should NOT trigger when Shift is missing"
+    );
+}
+
+#[test]
+fn test_any_of_modifiers_single_modifier() {
+    // Hotkey: (Control or Alt) + A
+    let hotkey = Hotkey::new(VKey::A, [VKey::Control, VKey::Menu], || {})
+        .modifier_match(ModifierMatch::AnyOf);
+
+    let mut ctrl_state = KeyboardState::new();
+    ctrl_state.keydown(VKey::LControl);
+    ctrl_state.keydown(VKey::A);
+
+    let mut alt_state = KeyboardState::new();
+    alt_state.keydown(VKey::RMenu);
+    alt_state.keydown(VKey::A);
+
+    let mut both_state = KeyboardState::new();
+    both_state.keydown(VKey::LControl);
+    both_state.keydown(VKey::LMenu);
+    both_state.keydown(VKey::A);
+
+    assert!(hotkey.is_trigger_state(&VKey::A, &ctrl_state));
+    assert!(hotkey.is_trigger_state(&VKey::A, &alt_state));
+    assert!(
+        hotkey.is_trigger_state(&VKey::A, &both_state),
+        "AnyOf should trigger when several of the listed modifiers are held"
+    );
+}
+
+#[test]
+fn test_any_of_modifiers_requires_one_modifier() {
+    // Hotkey: (Control or Alt) + A
+    let hotkey = Hotkey::new(VKey::A, [VKey::Control, VKey::Menu], || {})
+        .modifier_match(ModifierMatch::AnyOf);
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::A);
+
+    assert!(
+        !hotkey.is_trigger_state(&VKey::A, &state),
+        "AnyOf should NOT trigger without any of the listed modifiers"
+    );
+}
+
+#[test]
+fn test_any_of_modifiers_rejects_unlisted_modifier() {
+    // Hotkey: (Control or Alt) + A
+    let hotkey = Hotkey::new(VKey::A, [VKey::Control, VKey::Menu], || {})
+        .modifier_match(ModifierMatch::AnyOf);
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::LControl);
+    state.keydown(VKey::LShift);
+    state.keydown(VKey::A);
+
+    assert!(
+        !hotkey.is_trigger_state(&VKey::A, &state),
+        "AnyOf should NOT trigger when a modifier outside the list is held"
+    );
+}
+
+#[test]
+fn test_at_least_with_strict_sequence() {
+    // Hotkey: Control + A (strict sequence, tolerating other modifiers)
+    let hotkey = Hotkey::new(VKey::A, [VKey::Control], || {})
+        .modifier_match(ModifierMatch::AtLeast)
+        .strict_sequence();
+
+    let mut exact_state = KeyboardState::new();
+    exact_state.keydown(VKey::Control);
+    exact_state.keydown(VKey::A);
+
+    let mut extra_state = KeyboardState::new();
+    extra_state.keydown(VKey::Control);
+    extra_state.keydown(VKey::Shift);
+    extra_state.keydown(VKey::A);
+
+    assert!(hotkey.is_trigger_state(&VKey::A, &exact_state));
+    assert!(
+        !hotkey.is_trigger_state(&VKey::A, &extra_state),
+        "The strict sequence is still checked with relaxed modifiers"
+    );
+}
+
+#[test]
+fn test_at_least_on_key_up() {
+    // Hotkey: Control + A (trigger on key up, tolerating other modifiers)
+    let hotkey = Hotkey::new(VKey::A, [VKey::Control], || {})
+        .modifier_match(ModifierMatch::AtLeast)
+        .trigger_timing(TriggerTiming::OnKeyUp);
+
+    let mut state = KeyboardState::new();
+    state.keydown(VKey::Control);
+    state.keydown(VKey::Shift);
+    state.keydown(VKey::A);
+    state.keyup(VKey::A);
+
+    assert!(hotkey.is_trigger_state(&VKey::A, &state));
+}
+
+#[test]
+fn test_modifier_match_modes_are_distinct_hotkeys() {
+    let exact = Hotkey::new(VKey::A, [VKey::Control], || {});
+    let relaxed =
+        Hotkey::new(VKey::A, [VKey::Control], || {}).modifier_match(ModifierMatch::AtLeast);
+
+    assert_ne!(
+        exact, relaxed,
+        "Exact and relaxed bindings of the same combo can be registered together"
+    );
+    assert!(
+        ModifierMatch::Exact < ModifierMatch::AtLeast
+            && ModifierMatch::AtLeast < ModifierMatch::AnyOf,
+        "Stricter modes are resolved first"
+    );
+}